@@ -6,7 +6,10 @@ fn main() {
     tonic_build::configure()
         .out_dir("src/pb")
         .with_sql_type(&["reservation.ReservationStatus"])
-        .with_builder(&["reservation.ReservationQuery"])
+        .with_builder(&[
+            "reservation.ReservationQuery",
+            "reservation.ReservationFilter",
+        ])
         .with_builder_into(
             "reservation.ReservationQuery",
             &[
@@ -16,9 +19,24 @@ fn main() {
                 "page",
                 "page_size",
                 "desc",
+                "statuses",
+                "mode",
             ],
         )
         .with_builder_option("reservation.ReservationQuery", &["start", "end"])
+        .with_builder_option("reservation.ReservationFilter", &["start", "end"])
+        .with_builder_into(
+            "reservation.ReservationFilter",
+            &[
+                "resource_id",
+                "user_id",
+                "status",
+                "cursor",
+                "page_size",
+                "desc",
+                "statuses",
+            ],
+        )
         .compile(&["protos/reservation.proto"], &["protos"])
         .unwrap();
 
@@ -58,7 +76,7 @@ impl BuilderExt for Builder {
         fields.iter().fold(self, |acc, field| {
             acc.field_attribute(
                 format!("{}.{}", path, field),
-                "#[builder(setter(into,strip_option))]",
+                "#[builder(setter(into,strip_option),default)]",
             )
         })
     }
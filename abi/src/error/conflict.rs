@@ -1,12 +1,25 @@
 use chrono::{DateTime, Utc};
 use regex::Regex;
 use std::collections::HashMap;
+use std::ops::Range;
 use std::{convert::Infallible, str::FromStr};
 
+use crate::{utils::convert_to_timestamp, ConflictDetails, ConflictWindow, Reservation};
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum ReservationConflictInfo {
-    Parsed(ReservationConflict),
+    Parsed(Box<ReservationConflict>),
     Unparsed(String),
+    Capacity(CapacityConflict),
+}
+
+/// A reservation that would overlap more active reservations than the resource holds
+#[derive(Debug, Clone, PartialEq)]
+pub struct CapacityConflict {
+    pub new: ReservationWindow,
+    pub capacity: i32,
+    /// active reservations overlapping the new window
+    pub overlapping: Vec<Reservation>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -20,6 +33,9 @@ pub struct ReservationWindow {
     pub rid: String,
     pub start: DateTime<Utc>,
     pub end: DateTime<Utc>,
+    /// the window extended by the buffers, which is what may not overlap. `None` if the
+    /// reservation has no buffers
+    pub buffered: Option<Range<DateTime<Utc>>>,
 }
 
 impl FromStr for ReservationConflictInfo {
@@ -27,7 +43,7 @@ impl FromStr for ReservationConflictInfo {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         if let Ok(conflict) = s.parse() {
-            Ok(ReservationConflictInfo::Parsed(conflict))
+            Ok(ReservationConflictInfo::Parsed(Box::new(conflict)))
         } else {
             Ok(ReservationConflictInfo::Unparsed(s.to_string()))
         }
@@ -42,6 +58,20 @@ impl FromStr for ReservationConflict {
     }
 }
 
+impl ReservationConflict {
+    /// The exclusion constraint only reports the buffered windows, replace them with the
+    /// booked ones, known to the caller. A window stays buffered if its booking is unknown
+    pub fn booked(self, new: Range<DateTime<Utc>>, old: Option<Range<DateTime<Utc>>>) -> Self {
+        Self {
+            new: self.new.booked(new),
+            old: match old {
+                Some(old) => self.old.booked(old),
+                None => self.old,
+            },
+        }
+    }
+}
+
 impl TryFrom<ParsedInfo> for ReservationConflict {
     type Error = ();
 
@@ -57,7 +87,13 @@ impl TryFrom<HashMap<String, String>> for ReservationWindow {
     type Error = ();
 
     fn try_from(value: HashMap<String, String>) -> Result<Self, Self::Error> {
-        let timespan_str = value.get("timespan").ok_or(())?.replace('"', "");
+        // the exclusion constraint is on the timespan extended by the buffers, which only
+        // stands in for the booked window until it is known
+        let (timespan_str, buffered) = match value.get("timespan") {
+            Some(timespan) => (timespan, false),
+            None => (value.get("effective_timespan").ok_or(())?, true),
+        };
+        let timespan_str = timespan_str.replace('"', "");
         let mut split = timespan_str.splitn(2, ',');
         let start = parse_datetime(split.next().ok_or(())?)?;
         let end = parse_datetime(split.next().ok_or(())?)?;
@@ -65,10 +101,89 @@ impl TryFrom<HashMap<String, String>> for ReservationWindow {
             rid: value.get("resource_id").ok_or(())?.to_string(),
             start,
             end,
+            buffered: buffered.then_some(start..end),
         })
     }
 }
 
+impl ReservationWindow {
+    /// The window of the booking, keeping the parsed one as the buffered window
+    pub fn booked(self, booked: Range<DateTime<Utc>>) -> Self {
+        let buffered = self.buffered.filter(|buffered| *buffered != booked);
+        Self {
+            start: booked.start,
+            end: booked.end,
+            buffered,
+            ..self
+        }
+    }
+}
+
+impl CapacityConflict {
+    pub fn new(
+        rid: impl Into<String>,
+        window: Range<DateTime<Utc>>,
+        capacity: i32,
+        overlapping: Vec<Reservation>,
+    ) -> Self {
+        Self {
+            new: ReservationWindow {
+                rid: rid.into(),
+                start: window.start,
+                end: window.end,
+                buffered: None,
+            },
+            capacity,
+            overlapping,
+        }
+    }
+}
+
+impl From<ReservationConflict> for ConflictDetails {
+    fn from(conflict: ReservationConflict) -> Self {
+        Self {
+            new: Some(conflict.new.into()),
+            old: Some(conflict.old.into()),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<CapacityConflict> for ConflictDetails {
+    fn from(conflict: CapacityConflict) -> Self {
+        // old is the first reservation in the way, for clients expecting a single one
+        let old = conflict.overlapping.first().map(|rsvp| ConflictWindow {
+            resource_id: rsvp.resource_id.clone(),
+            start: rsvp.start.clone(),
+            end: rsvp.end.clone(),
+            ..Default::default()
+        });
+        Self {
+            new: Some(conflict.new.into()),
+            old,
+            capacity: conflict.capacity,
+            overlapping: conflict.overlapping,
+        }
+    }
+}
+
+impl From<ReservationWindow> for ConflictWindow {
+    fn from(window: ReservationWindow) -> Self {
+        Self {
+            resource_id: window.rid,
+            start: Some(convert_to_timestamp(window.start)),
+            end: Some(convert_to_timestamp(window.end)),
+            buffered_start: window
+                .buffered
+                .as_ref()
+                .map(|buffered| convert_to_timestamp(buffered.start)),
+            buffered_end: window
+                .buffered
+                .map(|buffered| convert_to_timestamp(buffered.end)),
+        }
+    }
+}
+
 struct ParsedInfo {
     new: HashMap<String, String>,
     old: HashMap<String, String>,
@@ -110,7 +225,9 @@ fn parse_datetime(s: &str) -> Result<DateTime<Utc>, ()> {
 mod tests {
     use std::str::FromStr;
 
-    use crate::ReservationConflictInfo;
+    use chrono::{DateTime, Utc};
+
+    use crate::{ConflictDetails, ReservationConflictInfo};
 
     use super::{ParsedInfo, ReservationWindow};
 
@@ -143,6 +260,42 @@ mod tests {
         }
     }
 
+    const BUFFERED_ERR_MSG: &str = "Key (resource_id, effective_timespan)=(ocean-view-room-715, [\"2023-01-03 11:15:00+00\",\"2023-01-03 12:15:00+00\")) conflicts with existing key (resource_id, effective_timespan)=(ocean-view-room-715, [\"2023-01-03 09:45:00+00\",\"2023-01-03 11:30:00+00\")).";
+
+    #[test]
+    fn buffered_conflict_should_report_booked_windows() {
+        let info: ReservationConflictInfo = BUFFERED_ERR_MSG.parse().unwrap();
+        let ReservationConflictInfo::Parsed(conflict) = info else {
+            panic!("expect parsed conflict, got {:?}", info);
+        };
+        let at = |s: &str| s.parse::<DateTime<Utc>>().unwrap();
+        // the constraint only knows the buffered windows
+        assert_eq!(
+            conflict.old.buffered,
+            Some(at("2023-01-03T09:45:00Z")..at("2023-01-03T11:30:00Z"))
+        );
+
+        let conflict = conflict.booked(
+            at("2023-01-03T11:30:00Z")..at("2023-01-03T12:00:00Z"),
+            Some(at("2023-01-03T10:00:00Z")..at("2023-01-03T11:00:00Z")),
+        );
+        assert_eq!(conflict.old.start, at("2023-01-03T10:00:00Z"));
+        assert_eq!(conflict.old.end, at("2023-01-03T11:00:00Z"));
+        assert_eq!(
+            conflict.old.buffered,
+            Some(at("2023-01-03T09:45:00Z")..at("2023-01-03T11:30:00Z"))
+        );
+        assert_eq!(conflict.new.start, at("2023-01-03T11:30:00Z"));
+
+        let details = ConflictDetails::from(conflict);
+        let old = details.old.unwrap();
+        assert_eq!(old.start.unwrap().to_string(), "2023-01-03T10:00:00Z");
+        assert_eq!(
+            old.buffered_start.unwrap().to_string(),
+            "2023-01-03T09:45:00Z"
+        );
+    }
+
     #[test]
     fn parser_to_conflict_should_work() {
         let info: Result<ReservationConflictInfo, _> = ERR_MSG.parse();
@@ -1,9 +1,12 @@
+use prost::Message;
 use sqlx::postgres::PgDatabaseError;
 use thiserror::Error;
 
 mod conflict;
 
-pub use conflict::ReservationConflictInfo;
+pub use conflict::{CapacityConflict, ReservationConflictInfo};
+
+use crate::{ConflictDetails, ReservationStatus};
 
 #[derive(Error, Debug)]
 pub enum Error {
@@ -28,6 +31,75 @@ pub enum Error {
     #[error("Invalid resource id: {0}")]
     InvalidResourceId(String),
 
+    #[error("Invalid cursor: {0}")]
+    InvalidCursor(String),
+
+    #[error("Invalid field mask: {0}")]
+    InvalidFieldMask(String),
+
+    #[error("Invalid hold ttl, it must be positive")]
+    InvalidHoldTtl,
+
+    #[error("The reservation hold has expired")]
+    HoldExpired,
+
+    #[error("Reservation not made, another one in the batch failed")]
+    BatchAborted,
+
+    #[error("Idempotency key {0} was already used for another request")]
+    IdempotencyKeyReused(String),
+
+    #[error("Reservation was changed concurrently, current version is {current}")]
+    VersionMismatch { current: i64 },
+
+    #[error("Invalid resource name: {0}")]
+    InvalidResourceName(String),
+
+    #[error("Invalid timezone: {0}")]
+    InvalidTimezone(String),
+
+    #[error("No resource found by id {0}")]
+    ResourceNotFound(String),
+
+    #[error("Resource {0} already exists")]
+    ResourceAlreadyExists(String),
+
+    #[error("Resource {0} is inactive and can't be reserved")]
+    ResourceInactive(String),
+
+    #[error("Invalid booking rules: {0}")]
+    InvalidBookingRules(String),
+
+    #[error("Reservation is shorter than the minimum of {min} seconds")]
+    ReservationTooShort { min: i64 },
+
+    #[error("Reservation is longer than the maximum of {max} seconds")]
+    ReservationTooLong { max: i64 },
+
+    #[error("Reservation must be made at least {min} seconds before it starts")]
+    LeadTimeTooShort { min: i64 },
+
+    #[error("Reservation can't be made more than {max} seconds before it starts")]
+    BeyondBookingHorizon { max: i64 },
+
+    #[error("Reservation start and end must be aligned to {alignment} seconds")]
+    MisalignedReservation { alignment: i64 },
+
+    #[error("Invalid recurrence rule: {0}")]
+    InvalidRecurrenceRule(String),
+
+    #[error("Invalid capacity {0}, it must be positive")]
+    InvalidCapacity(i32),
+
+    #[error("Capacity {capacity} is below the {overlapping} reservations overlapping at once")]
+    CapacityTooLow { capacity: i32, overlapping: i64 },
+
+    #[error("Invalid status transition from {from} to {to}")]
+    InvalidStatusTransition {
+        from: ReservationStatus,
+        to: ReservationStatus,
+    },
+
     #[error("unknown error")]
     Unknown,
 }
@@ -40,7 +112,53 @@ impl PartialEq for Error {
             (Self::InvalidUserId(l0), Self::InvalidUserId(r0)) => l0 == r0,
             (Self::InvalidReservationId(l0), Self::InvalidReservationId(r0)) => l0 == r0,
             (Self::InvalidResourceId(l0), Self::InvalidResourceId(r0)) => l0 == r0,
+            (Self::InvalidCursor(l0), Self::InvalidCursor(r0)) => l0 == r0,
+            (Self::InvalidFieldMask(l0), Self::InvalidFieldMask(r0)) => l0 == r0,
+            (Self::InvalidResourceName(l0), Self::InvalidResourceName(r0)) => l0 == r0,
+            (Self::InvalidTimezone(l0), Self::InvalidTimezone(r0)) => l0 == r0,
+            (Self::ResourceNotFound(l0), Self::ResourceNotFound(r0)) => l0 == r0,
+            (Self::ResourceAlreadyExists(l0), Self::ResourceAlreadyExists(r0)) => l0 == r0,
+            (Self::ResourceInactive(l0), Self::ResourceInactive(r0)) => l0 == r0,
+            (Self::InvalidBookingRules(l0), Self::InvalidBookingRules(r0)) => l0 == r0,
+            (Self::InvalidCapacity(l0), Self::InvalidCapacity(r0)) => l0 == r0,
+            (
+                Self::CapacityTooLow {
+                    capacity: c0,
+                    overlapping: o0,
+                },
+                Self::CapacityTooLow {
+                    capacity: c1,
+                    overlapping: o1,
+                },
+            ) => c0 == c1 && o0 == o1,
+            (Self::InvalidRecurrenceRule(l0), Self::InvalidRecurrenceRule(r0)) => l0 == r0,
+            (Self::ReservationTooShort { min: l0 }, Self::ReservationTooShort { min: r0 }) => {
+                l0 == r0
+            }
+            (Self::ReservationTooLong { max: l0 }, Self::ReservationTooLong { max: r0 }) => {
+                l0 == r0
+            }
+            (Self::LeadTimeTooShort { min: l0 }, Self::LeadTimeTooShort { min: r0 }) => l0 == r0,
+            (Self::BeyondBookingHorizon { max: l0 }, Self::BeyondBookingHorizon { max: r0 }) => {
+                l0 == r0
+            }
+            (
+                Self::MisalignedReservation { alignment: l0 },
+                Self::MisalignedReservation { alignment: r0 },
+            ) => l0 == r0,
             (Self::NotFound, Self::NotFound) => true,
+            (Self::InvalidTime, Self::InvalidTime) => true,
+            (Self::InvalidHoldTtl, Self::InvalidHoldTtl) => true,
+            (Self::HoldExpired, Self::HoldExpired) => true,
+            (Self::BatchAborted, Self::BatchAborted) => true,
+            (Self::IdempotencyKeyReused(l0), Self::IdempotencyKeyReused(r0)) => l0 == r0,
+            (Self::VersionMismatch { current: l0 }, Self::VersionMismatch { current: r0 }) => {
+                l0 == r0
+            }
+            (
+                Self::InvalidStatusTransition { from: f0, to: t0 },
+                Self::InvalidStatusTransition { from: f1, to: t1 },
+            ) => f0 == f1 && t0 == t1,
             _ => false,
         }
     }
@@ -51,8 +169,10 @@ impl From<sqlx::Error> for Error {
         match e {
             sqlx::Error::Database(e) => {
                 let err: &PgDatabaseError = e.downcast_ref();
-                match (err.code(), err.schema(), err.table()) {
-                    ("23P01", Some("rsvp"), Some("reservations")) => {
+                // the exclusion constraint only covers active (pending / confirmed) rows,
+                // any violation of it is still a conflict with an active reservation
+                match (err.code(), err.schema(), err.table(), err.constraint()) {
+                    ("23P01", Some("rsvp"), Some("reservations"), Some("reservation_conflict")) => {
                         Error::ConflictReservation(err.detail().unwrap().parse().unwrap())
                     }
                     _ => Error::DbError(sqlx::Error::Database(e)),
@@ -63,3 +183,134 @@ impl From<sqlx::Error> for Error {
         }
     }
 }
+
+impl From<Error> for tonic::Status {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::DbError(_) | Error::Unknown => tonic::Status::internal(e.to_string()),
+            Error::NotFound | Error::ResourceNotFound(_) => tonic::Status::not_found(e.to_string()),
+            Error::ResourceAlreadyExists(_) => tonic::Status::already_exists(e.to_string()),
+            Error::InvalidTime
+            | Error::InvalidUserId(_)
+            | Error::InvalidReservationId(_)
+            | Error::InvalidResourceId(_)
+            | Error::InvalidCursor(_)
+            | Error::InvalidFieldMask(_)
+            | Error::InvalidHoldTtl
+            | Error::IdempotencyKeyReused(_)
+            | Error::InvalidResourceName(_)
+            | Error::InvalidTimezone(_)
+            | Error::InvalidBookingRules(_)
+            | Error::InvalidCapacity(_)
+            | Error::InvalidRecurrenceRule(_)
+            | Error::ReservationTooShort { .. }
+            | Error::ReservationTooLong { .. }
+            | Error::MisalignedReservation { .. } => tonic::Status::invalid_argument(e.to_string()),
+            Error::VersionMismatch { .. } | Error::BatchAborted => {
+                tonic::Status::aborted(e.to_string())
+            }
+            Error::HoldExpired
+            | Error::ResourceInactive(_)
+            | Error::LeadTimeTooShort { .. }
+            | Error::BeyondBookingHorizon { .. }
+            | Error::CapacityTooLow { .. }
+            | Error::InvalidStatusTransition { .. } => {
+                tonic::Status::failed_precondition(e.to_string())
+            }
+            Error::ConflictReservation(ReservationConflictInfo::Parsed(ref info)) => {
+                let details = ConflictDetails::from(info.as_ref().clone());
+                tonic::Status::with_details(
+                    tonic::Code::AlreadyExists,
+                    e.to_string(),
+                    details.encode_to_vec().into(),
+                )
+            }
+            Error::ConflictReservation(ReservationConflictInfo::Capacity(ref info)) => {
+                let details = ConflictDetails::from(info.clone());
+                tonic::Status::with_details(
+                    tonic::Code::AlreadyExists,
+                    format!("{}: capacity of {} reached", e, info.capacity),
+                    details.encode_to_vec().into(),
+                )
+            }
+            Error::ConflictReservation(ReservationConflictInfo::Unparsed(ref info)) => {
+                tonic::Status::failed_precondition(format!("{}: {}", e, info))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Utc};
+    use prost::Message;
+
+    use super::*;
+    use crate::error::conflict::{ReservationConflict, ReservationWindow};
+
+    fn window(start: &str, end: &str) -> ReservationWindow {
+        ReservationWindow {
+            rid: "ocean-view-room-713".to_string(),
+            start: start.parse::<DateTime<Utc>>().unwrap(),
+            end: end.parse::<DateTime<Utc>>().unwrap(),
+            buffered: None,
+        }
+    }
+
+    #[test]
+    fn error_to_status_should_map_codes() {
+        let status: tonic::Status = Error::NotFound.into();
+        assert_eq!(status.code(), tonic::Code::NotFound);
+
+        let status: tonic::Status = Error::InvalidTime.into();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+
+        let status: tonic::Status = Error::InvalidUserId("".to_string()).into();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+
+        let status: tonic::Status = Error::HoldExpired.into();
+        assert_eq!(status.code(), tonic::Code::FailedPrecondition);
+
+        let status: tonic::Status = Error::ResourceInactive("room".to_string()).into();
+        assert_eq!(status.code(), tonic::Code::FailedPrecondition);
+
+        let status: tonic::Status = Error::ReservationTooShort { min: 900 }.into();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+
+        let status: tonic::Status = Error::LeadTimeTooShort { min: 3600 }.into();
+        assert_eq!(status.code(), tonic::Code::FailedPrecondition);
+
+        let status: tonic::Status = Error::ResourceAlreadyExists("room".to_string()).into();
+        assert_eq!(status.code(), tonic::Code::AlreadyExists);
+
+        let status: tonic::Status = Error::VersionMismatch { current: 2 }.into();
+        assert_eq!(status.code(), tonic::Code::Aborted);
+
+        let status: tonic::Status = Error::DbError(sqlx::Error::PoolTimedOut).into();
+        assert_eq!(status.code(), tonic::Code::Internal);
+    }
+
+    #[test]
+    fn parsed_conflict_to_status_should_carry_details() {
+        let conflict = ReservationConflict {
+            new: window("2022-12-26T22:00:00Z", "2022-12-30T19:00:00Z"),
+            old: window("2022-12-25T22:00:00Z", "2022-12-28T19:00:00Z"),
+        };
+        let err = Error::ConflictReservation(ReservationConflictInfo::Parsed(Box::new(conflict)));
+        let status: tonic::Status = err.into();
+        assert_eq!(status.code(), tonic::Code::AlreadyExists);
+
+        let details = ConflictDetails::decode(status.details()).unwrap();
+        let old = details.old.unwrap();
+        assert_eq!(old.resource_id, "ocean-view-room-713");
+        assert_eq!(old.start.unwrap().seconds, 1672005600);
+    }
+
+    #[test]
+    fn unparsed_conflict_to_status_should_be_failed_precondition() {
+        let err = Error::ConflictReservation(ReservationConflictInfo::Unparsed("oops".into()));
+        let status: tonic::Status = err.into();
+        assert_eq!(status.code(), tonic::Code::FailedPrecondition);
+        assert!(status.details().is_empty());
+    }
+}
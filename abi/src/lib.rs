@@ -5,6 +5,7 @@ mod utils;
 
 pub use error::*;
 pub use pb::*;
+pub use types::{CursorDirection, FilterCursor, RecurrenceRule, ResourceField, UpdateField};
 
 pub trait Validator {
     fn validate(&self) -> Result<(), Error>;
@@ -17,4 +18,15 @@ enum RsvpStatus {
     Blocked,
     Confirmed,
     Unknown,
+    Cancelled,
+    Released,
+}
+
+#[derive(Debug, sqlx::Type)]
+#[sqlx(type_name = "reservation_update_type", rename_all = "lowercase")]
+enum RsvpUpdateType {
+    Unknown,
+    Create,
+    Update,
+    Delete,
 }
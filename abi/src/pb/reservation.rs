@@ -25,6 +25,19 @@ pub struct Reservation {
     /// extra note
     #[prost(string, tag = "7")]
     pub note: ::prost::alloc::string::String,
+    /// how long a pending reservation is held before it expires and gets cancelled.
+    /// If not set, the hold never expires
+    #[prost(message, optional, tag = "8")]
+    pub hold_ttl: ::core::option::Option<::prost_types::Duration>,
+    /// when the pending hold expires, set by the server
+    #[prost(message, optional, tag = "9")]
+    pub expires_at: ::core::option::Option<::prost_types::Timestamp>,
+    /// incremented on every change, pass it as expected_version to reject stale writes
+    #[prost(int64, tag = "10")]
+    pub version: i64,
+    /// id of the recurring reservation this is an occurrence of, empty if it isn't one
+    #[prost(string, tag = "11")]
+    pub series_id: ::prost::alloc::string::String,
 }
 /// To make a reservation, send a ReserveRequest with Reservation object (id should be empty)
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -32,6 +45,10 @@ pub struct Reservation {
 pub struct ReserveRequest {
     #[prost(message, optional, tag = "1")]
     pub reservation: ::core::option::Option<Reservation>,
+    /// if set, a retried request with the same key returns the original reservation.
+    /// Reusing the key for another reservation is rejected
+    #[prost(string, tag = "2")]
+    pub idempotency_key: ::prost::alloc::string::String,
 }
 /// Created reservation will be returned in ReserveResponse
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -40,12 +57,101 @@ pub struct ReserveResponse {
     #[prost(message, optional, tag = "1")]
     pub reservation: ::core::option::Option<Reservation>,
 }
-/// To update a reservation, send an UpdateRequest, Only note is updatable.
+/// To make several reservations at once, send a ReserveBatchRequest
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
-pub struct UpdateRequest {
+pub struct ReserveBatchRequest {
+    #[prost(message, repeated, tag = "1")]
+    pub reservations: ::prost::alloc::vec::Vec<Reservation>,
+    #[prost(enumeration = "BatchMode", tag = "2")]
+    pub mode: i32,
+}
+/// Outcome of one reservation in a batch
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ReserveBatchResult {
+    #[prost(oneof = "reserve_batch_result::Result", tags = "1, 2, 3")]
+    pub result: ::core::option::Option<reserve_batch_result::Result>,
+}
+/// Nested message and enum types in `ReserveBatchResult`.
+pub mod reserve_batch_result {
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Result {
+        /// the reservation made
+        #[prost(message, tag = "1")]
+        Reservation(super::Reservation),
+        /// the reservation conflicts with an existing one, or one earlier in the batch
+        #[prost(message, tag = "2")]
+        Conflict(super::ConflictDetails),
+        /// any other failure, including an atomic batch aborted by another reservation
+        #[prost(string, tag = "3")]
+        Error(::prost::alloc::string::String),
+    }
+}
+/// Results of a batch, in the order of the reservations in the request
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ReserveBatchResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub results: ::prost::alloc::vec::Vec<ReserveBatchResult>,
+}
+/// A reservation repeated following a recurrence rule
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RecurringReservation {
+    /// the first occurrence. Its start and end give the local time of day, in the time zone
+    /// of the resource, and the length of every occurrence
+    #[prost(message, optional, tag = "1")]
+    pub reservation: ::core::option::Option<Reservation>,
+    /// RFC 5545 recurrence rule, e.g. "FREQ=WEEKLY;BYDAY=TU;COUNT=12". Supported parts are
+    /// FREQ (DAILY, WEEKLY or MONTHLY), INTERVAL, COUNT, UNTIL and BYDAY (weekly only, without
+    /// ordinals). Either COUNT or UNTIL is required. An UNTIL ending in Z is UTC, otherwise
+    /// it is local time in the time zone of the resource
+    #[prost(string, tag = "2")]
+    pub rrule: ::prost::alloc::string::String,
+}
+/// To make all occurrences of a recurring reservation, send a ReserveRecurringRequest.
+/// ATOMIC fails all occurrences if any fails, BEST_EFFORT skips the conflicting ones
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ReserveRecurringRequest {
+    #[prost(message, optional, tag = "1")]
+    pub recurring: ::core::option::Option<RecurringReservation>,
+    #[prost(enumeration = "BatchMode", tag = "2")]
+    pub mode: i32,
+}
+/// Outcome of each occurrence will be returned in ReserveRecurringResponse
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ReserveRecurringResponse {
+    /// series id of the occurrences made, empty if none was made
     #[prost(string, tag = "1")]
-    pub note: ::prost::alloc::string::String,
+    pub series_id: ::prost::alloc::string::String,
+    /// windows of the occurrences, in order
+    #[prost(message, repeated, tag = "2")]
+    pub occurrences: ::prost::alloc::vec::Vec<TimeSlot>,
+    /// result of each occurrence, in the order of occurrences
+    #[prost(message, repeated, tag = "3")]
+    pub results: ::prost::alloc::vec::Vec<ReserveBatchResult>,
+}
+/// To update a reservation, send an UpdateRequest with the fields to change listed
+/// in field_mask. Updatable fields are note and user_id
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UpdateRequest {
+    /// id of the reservation to update
+    #[prost(string, tag = "2")]
+    pub id: ::prost::alloc::string::String,
+    /// paths of the fields to take from reservation, e.g. \["note", "user_id"\]
+    #[prost(message, optional, tag = "3")]
+    pub field_mask: ::core::option::Option<::prost_types::FieldMask>,
+    /// new values of the fields in field_mask, other fields are ignored
+    #[prost(message, optional, tag = "4")]
+    pub reservation: ::core::option::Option<Reservation>,
+    /// if set, the update fails unless the reservation is still at this version
+    #[prost(int64, optional, tag = "5")]
+    pub expected_version: ::core::option::Option<i64>,
 }
 /// Updated reservation will be returned in UpdateResponse
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -60,6 +166,13 @@ pub struct UpdateResponse {
 pub struct ConfirmRequest {
     #[prost(message, optional, tag = "1")]
     pub reservation: ::core::option::Option<Reservation>,
+    /// if set, confirming fails unless the reservation is still at this version
+    #[prost(int64, optional, tag = "2")]
+    pub expected_version: ::core::option::Option<i64>,
+    /// if set, a retried request with the same key returns the original reservation.
+    /// Reusing the key for another reservation id is rejected
+    #[prost(string, tag = "3")]
+    pub idempotency_key: ::prost::alloc::string::String,
 }
 /// Confirmed reservation will be returned in ConfirmResponse
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -74,6 +187,9 @@ pub struct ConfirmResponse {
 pub struct CancelRequest {
     #[prost(string, tag = "1")]
     pub id: ::prost::alloc::string::String,
+    /// if set, cancelling fails unless the reservation is still at this version
+    #[prost(int64, optional, tag = "2")]
+    pub expected_version: ::core::option::Option<i64>,
 }
 /// Canceled reservation will be returned in CancelResponse
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -112,19 +228,19 @@ pub struct ReservationQuery {
     #[prost(enumeration = "ReservationStatus", tag = "3")]
     #[builder(setter(into), default)]
     pub status: i32,
-    /// start time for the reservation query. if 0, use Infinity for start time
+    /// start time for the reservation query. if 0 or not set, use -Infinity for start time
     #[prost(message, optional, tag = "4")]
-    #[builder(setter(into, strip_option))]
+    #[builder(setter(into, strip_option), default)]
     pub start: ::core::option::Option<::prost_types::Timestamp>,
-    /// end time for the reservation query. if 0, use Infinity for end time
+    /// end time for the reservation query. if 0 or not set, use Infinity for end time
     #[prost(message, optional, tag = "5")]
-    #[builder(setter(into, strip_option))]
+    #[builder(setter(into, strip_option), default)]
     pub end: ::core::option::Option<::prost_types::Timestamp>,
     /// current page for reservation query
     #[prost(int32, tag = "6")]
     #[builder(setter(into), default)]
     pub page: i32,
-    /// page size for the query
+    /// page size for the query, 10 if not set. Sizes outside 10 to 100 are clamped to it
     #[prost(int32, tag = "7")]
     #[builder(setter(into), default)]
     pub page_size: i32,
@@ -132,6 +248,14 @@ pub struct ReservationQuery {
     #[prost(bool, tag = "8")]
     #[builder(setter(into), default)]
     pub desc: bool,
+    /// match any of the given statuses, together with status. If empty, status alone is used
+    #[prost(enumeration = "ReservationStatus", repeated, tag = "9")]
+    #[builder(setter(into), default)]
+    pub statuses: ::prost::alloc::vec::Vec<i32>,
+    /// how start and end are matched against reservations, CONTAINED by default
+    #[prost(enumeration = "QueryMode", tag = "10")]
+    #[builder(setter(into), default)]
+    pub mode: i32,
 }
 /// To query reservation, send a QueryRequest
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -140,10 +264,337 @@ pub struct QueryRequest {
     #[prost(message, optional, tag = "1")]
     pub query: ::core::option::Option<ReservationQuery>,
 }
+/// page metadata of a query, counted over all matching reservations
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct QueryPager {
+    /// number of reservations matching the query, regardless of pagination
+    #[prost(int64, tag = "1")]
+    pub total: i64,
+    /// page being returned, starting from 1
+    #[prost(int32, tag = "2")]
+    pub page: i32,
+    /// page size actually used for the query
+    #[prost(int32, tag = "3")]
+    pub page_size: i32,
+    /// whether there are more reservations after this page
+    #[prost(bool, tag = "4")]
+    pub has_next: bool,
+}
+/// Queried reservations are streamed in QueryResponse, the pager comes first
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct QueryResponse {
+    #[prost(oneof = "query_response::Data", tags = "1, 2")]
+    pub data: ::core::option::Option<query_response::Data>,
+}
+/// Nested message and enum types in `QueryResponse`.
+pub mod query_response {
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Data {
+        #[prost(message, tag = "1")]
+        Pager(super::QueryPager),
+        #[prost(message, tag = "2")]
+        Reservation(super::Reservation),
+    }
+}
+/// query reservations ordered by start time and id, paginated with a cursor
+#[derive(derive_builder::Builder)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ReservationFilter {
+    /// resource id for the reservation query. If empty, query all resources
+    #[prost(string, tag = "1")]
+    #[builder(setter(into), default)]
+    pub resource_id: ::prost::alloc::string::String,
+    /// user id for the reservation query. If empty, query all users
+    #[prost(string, tag = "2")]
+    #[builder(setter(into), default)]
+    pub user_id: ::prost::alloc::string::String,
+    /// use status to filter result. If UNKNOWN, return all reservations
+    #[prost(enumeration = "ReservationStatus", tag = "3")]
+    #[builder(setter(into), default)]
+    pub status: i32,
+    /// opaque cursor taken from FilterPager. If empty, start from the first page
+    #[prost(string, tag = "4")]
+    #[builder(setter(into), default)]
+    pub cursor: ::prost::alloc::string::String,
+    /// page size for the query, 10 if not set. Sizes outside 10 to 100 are clamped to it
+    #[prost(int32, tag = "5")]
+    #[builder(setter(into), default)]
+    pub page_size: i32,
+    /// sort by start time in descending order
+    #[prost(bool, tag = "6")]
+    #[builder(setter(into), default)]
+    pub desc: bool,
+    /// match any of the given statuses, together with status. If empty, status alone is used
+    #[prost(enumeration = "ReservationStatus", repeated, tag = "7")]
+    #[builder(setter(into), default)]
+    pub statuses: ::prost::alloc::vec::Vec<i32>,
+    /// only reservations lying entirely within the window are returned. If 0 or not set, use
+    /// -Infinity for start time
+    #[prost(message, optional, tag = "8")]
+    #[builder(setter(into, strip_option), default)]
+    pub start: ::core::option::Option<::prost_types::Timestamp>,
+    /// if 0 or not set, use Infinity for end time
+    #[prost(message, optional, tag = "9")]
+    #[builder(setter(into, strip_option), default)]
+    pub end: ::core::option::Option<::prost_types::Timestamp>,
+}
+/// To filter reservations, send a FilterRequest
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct FilterRequest {
+    #[prost(message, optional, tag = "1")]
+    pub filter: ::core::option::Option<ReservationFilter>,
+}
+/// cursors to navigate between pages, empty if there is no such page
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct FilterPager {
+    #[prost(string, tag = "1")]
+    pub prev: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub next: ::prost::alloc::string::String,
+}
+/// Filtered reservations will be returned in FilterResponse
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct FilterResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub reservations: ::prost::alloc::vec::Vec<Reservation>,
+    #[prost(message, optional, tag = "2")]
+    pub pager: ::core::option::Option<FilterPager>,
+}
+/// To find the free slots of a resource, send a FindAvailabilityRequest. A slot is free
+/// while fewer active reservations than the capacity of the resource overlap it
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct FindAvailabilityRequest {
+    /// resource to look at
+    #[prost(string, tag = "1")]
+    pub resource_id: ::prost::alloc::string::String,
+    /// start of the window to search, required
+    #[prost(message, optional, tag = "2")]
+    pub start: ::core::option::Option<::prost_types::Timestamp>,
+    /// end of the window to search, required
+    #[prost(message, optional, tag = "3")]
+    pub end: ::core::option::Option<::prost_types::Timestamp>,
+    /// slots shorter than this are left out. If not set, any free slot is returned
+    #[prost(message, optional, tag = "4")]
+    pub min_duration: ::core::option::Option<::prost_types::Duration>,
+    /// slot bounds are rounded inwards to multiples of this, counted from the unix epoch.
+    /// If not set, slots are not rounded
+    #[prost(message, optional, tag = "5")]
+    pub granularity: ::core::option::Option<::prost_types::Duration>,
+}
+/// A span of time, start inclusive and end exclusive
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TimeSlot {
+    #[prost(message, optional, tag = "1")]
+    pub start: ::core::option::Option<::prost_types::Timestamp>,
+    #[prost(message, optional, tag = "2")]
+    pub end: ::core::option::Option<::prost_types::Timestamp>,
+}
+/// Free slots will be returned in FindAvailabilityResponse, ordered by start time
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct FindAvailabilityResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub slots: ::prost::alloc::vec::Vec<TimeSlot>,
+}
+/// A resource that can be reserved, e.g. a room
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Resource {
+    /// unique id of the resource, chosen by the creator
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+    /// human readable name
+    #[prost(string, tag = "2")]
+    pub name: ::prost::alloc::string::String,
+    /// kind of resource, e.g. "room" or "desk", used to group resources
+    #[prost(string, tag = "3")]
+    pub kind: ::prost::alloc::string::String,
+    /// IANA time zone name of the resource, e.g. "America/Los_Angeles". UTC if empty
+    #[prost(string, tag = "4")]
+    pub timezone: ::prost::alloc::string::String,
+    /// free-form attributes, e.g. {"floor": "7"}
+    #[prost(map = "string, string", tag = "5")]
+    pub attributes:
+        ::std::collections::HashMap<::prost::alloc::string::String, ::prost::alloc::string::String>,
+    /// inactive resources keep their reservations but can't be reserved any more
+    #[prost(bool, tag = "6")]
+    pub active: bool,
+    /// rules reservations of the resource must follow. If not set, any reservation is allowed
+    #[prost(message, optional, tag = "7")]
+    pub rules: ::core::option::Option<BookingRules>,
+    /// how many active reservations may overlap at any time. 1 if not set
+    #[prost(int32, tag = "8")]
+    pub capacity: i32,
+}
+/// Rules checked when a resource is reserved, a rule that is not set is not enforced.
+/// Blocked reservations are exempt, they are made by admins
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BookingRules {
+    /// shortest reservation allowed
+    #[prost(message, optional, tag = "1")]
+    pub min_duration: ::core::option::Option<::prost_types::Duration>,
+    /// longest reservation allowed
+    #[prost(message, optional, tag = "2")]
+    pub max_duration: ::core::option::Option<::prost_types::Duration>,
+    /// how long before its start a reservation must be made at least
+    #[prost(message, optional, tag = "3")]
+    pub min_lead_time: ::core::option::Option<::prost_types::Duration>,
+    /// how far ahead of its start a reservation can be made at most
+    #[prost(message, optional, tag = "4")]
+    pub max_horizon: ::core::option::Option<::prost_types::Duration>,
+    /// start and end must be multiples of this in the local time of the resource, counted
+    /// from midnight of the unix epoch there, e.g. 15 minutes
+    #[prost(message, optional, tag = "5")]
+    pub slot_alignment: ::core::option::Option<::prost_types::Duration>,
+    /// time the resource is taken before each reservation, e.g. for setup. Reservations are
+    /// taken with their buffers for every resource: with a capacity of 1 they conflict if
+    /// their extended windows overlap, above 1 the extended windows count against capacity
+    #[prost(message, optional, tag = "7")]
+    pub pre_buffer: ::core::option::Option<::prost_types::Duration>,
+    /// time the resource is taken after each reservation, e.g. for cleaning
+    #[prost(message, optional, tag = "8")]
+    pub post_buffer: ::core::option::Option<::prost_types::Duration>,
+}
+/// To add a resource to the catalog, send a CreateResourceRequest. active is ignored,
+/// new resources are always active
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CreateResourceRequest {
+    #[prost(message, optional, tag = "1")]
+    pub resource: ::core::option::Option<Resource>,
+}
+/// Created resource will be returned in CreateResourceResponse
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CreateResourceResponse {
+    #[prost(message, optional, tag = "1")]
+    pub resource: ::core::option::Option<Resource>,
+}
+/// To get a resource, send a GetResourceRequest
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetResourceRequest {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+}
+/// Resource will be returned in GetResourceResponse
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetResourceResponse {
+    #[prost(message, optional, tag = "1")]
+    pub resource: ::core::option::Option<Resource>,
+}
+/// To list resources, send a ListResourcesRequest
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListResourcesRequest {
+    /// only list resources of this kind. If empty, list all kinds
+    #[prost(string, tag = "1")]
+    pub kind: ::prost::alloc::string::String,
+    /// list inactive resources too
+    #[prost(bool, tag = "2")]
+    pub include_inactive: bool,
+}
+/// Resources will be returned in ListResourcesResponse, ordered by id
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListResourcesResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub resources: ::prost::alloc::vec::Vec<Resource>,
+}
+/// To update a resource, send an UpdateResourceRequest with the fields to change listed
+/// in field_mask. Updatable fields are name, kind, timezone, attributes, active, rules
+/// and capacity
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UpdateResourceRequest {
+    /// id of the resource to update
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+    /// paths of the fields to take from resource, e.g. \["name", "attributes"\]
+    #[prost(message, optional, tag = "2")]
+    pub field_mask: ::core::option::Option<::prost_types::FieldMask>,
+    /// new values of the fields in field_mask, other fields are ignored
+    #[prost(message, optional, tag = "3")]
+    pub resource: ::core::option::Option<Resource>,
+}
+/// Updated resource will be returned in UpdateResourceResponse
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UpdateResourceResponse {
+    #[prost(message, optional, tag = "1")]
+    pub resource: ::core::option::Option<Resource>,
+}
+/// To stop a resource from being reserved, send a DeactivateResourceRequest
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeactivateResourceRequest {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+}
+/// Deactivated resource will be returned in DeactivateResourceResponse
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeactivateResourceResponse {
+    #[prost(message, optional, tag = "1")]
+    pub resource: ::core::option::Option<Resource>,
+}
+/// A reservation window on a resource, used to describe a conflict
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ConflictWindow {
+    /// resource id of the window
+    #[prost(string, tag = "1")]
+    pub resource_id: ::prost::alloc::string::String,
+    /// start time of the window
+    #[prost(message, optional, tag = "2")]
+    pub start: ::core::option::Option<::prost_types::Timestamp>,
+    /// end time of the window
+    #[prost(message, optional, tag = "3")]
+    pub end: ::core::option::Option<::prost_types::Timestamp>,
+    /// the window extended by the pre and post buffers, which is what may not overlap.
+    /// Not set if the reservation has no buffers
+    #[prost(message, optional, tag = "4")]
+    pub buffered_start: ::core::option::Option<::prost_types::Timestamp>,
+    #[prost(message, optional, tag = "5")]
+    pub buffered_end: ::core::option::Option<::prost_types::Timestamp>,
+}
+/// Attached as binary status details when a reservation conflicts with an existing one
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ConflictDetails {
+    /// the window that was requested
+    #[prost(message, optional, tag = "1")]
+    pub new: ::core::option::Option<ConflictWindow>,
+    /// the existing window it collides with
+    #[prost(message, optional, tag = "2")]
+    pub old: ::core::option::Option<ConflictWindow>,
+    /// capacity of the resource, only set if the resource is full
+    #[prost(int32, tag = "3")]
+    pub capacity: i32,
+    /// active reservations overlapping the requested window, only set if the resource is full
+    #[prost(message, repeated, tag = "4")]
+    pub overlapping: ::prost::alloc::vec::Vec<Reservation>,
+}
 /// Client can listen to reservation updates by sending a ListenRequest
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
-pub struct ListenRequest {}
+pub struct ListenRequest {
+    /// resume after the given change id. Changes recorded after it are replayed
+    /// before live updates. If not set, only changes made after listening are sent
+    #[prost(int64, optional, tag = "1")]
+    pub since_change_id: ::core::option::Option<i64>,
+}
 /// Server will send ListenResponse to client in streaming response
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -154,6 +605,11 @@ pub struct ListenResponse {
     /// id for updated reservation
     #[prost(message, optional, tag = "2")]
     pub reservation: ::core::option::Option<Reservation>,
+    /// id of the change, use it as since_change_id to resume listening. Changes are sent
+    /// once final, in the order their transactions started, so a later change may have a
+    /// smaller id: resume from the last one received
+    #[prost(int64, tag = "3")]
+    pub change_id: i64,
 }
 /// reservation status for a given time period
 #[derive(
@@ -165,6 +621,8 @@ pub enum ReservationStatus {
     Pending = 1,
     Confirmed = 2,
     Blocked = 3,
+    Cancelled = 4,
+    Released = 5,
 }
 impl ReservationStatus {
     /// String value of the enum field names used in the ProtoBuf definition.
@@ -177,6 +635,8 @@ impl ReservationStatus {
             ReservationStatus::Pending => "RESERVATION_STATUS_PENDING",
             ReservationStatus::Confirmed => "RESERVATION_STATUS_CONFIRMED",
             ReservationStatus::Blocked => "RESERVATION_STATUS_BLOCKED",
+            ReservationStatus::Cancelled => "RESERVATION_STATUS_CANCELLED",
+            ReservationStatus::Released => "RESERVATION_STATUS_RELEASED",
         }
     }
     /// Creates an enum from field names used in the ProtoBuf definition.
@@ -186,6 +646,45 @@ impl ReservationStatus {
             "RESERVATION_STATUS_PENDING" => Some(Self::Pending),
             "RESERVATION_STATUS_CONFIRMED" => Some(Self::Confirmed),
             "RESERVATION_STATUS_BLOCKED" => Some(Self::Blocked),
+            "RESERVATION_STATUS_CANCELLED" => Some(Self::Cancelled),
+            "RESERVATION_STATUS_RELEASED" => Some(Self::Released),
+            _ => None,
+        }
+    }
+}
+/// how a reservation's time span is matched against the query window
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum QueryMode {
+    /// the reservation lies entirely within the window
+    Contained = 0,
+    /// any part of the reservation falls within the window
+    Overlapping = 1,
+    /// the reservation starts within the window
+    StartsWithin = 2,
+    /// the reservation ends within the window
+    EndsWithin = 3,
+}
+impl QueryMode {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            QueryMode::Contained => "QUERY_MODE_CONTAINED",
+            QueryMode::Overlapping => "QUERY_MODE_OVERLAPPING",
+            QueryMode::StartsWithin => "QUERY_MODE_STARTS_WITHIN",
+            QueryMode::EndsWithin => "QUERY_MODE_ENDS_WITHIN",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "QUERY_MODE_CONTAINED" => Some(Self::Contained),
+            "QUERY_MODE_OVERLAPPING" => Some(Self::Overlapping),
+            "QUERY_MODE_STARTS_WITHIN" => Some(Self::StartsWithin),
+            "QUERY_MODE_ENDS_WITHIN" => Some(Self::EndsWithin),
             _ => None,
         }
     }
@@ -223,6 +722,35 @@ impl ReservationUpdateType {
         }
     }
 }
+/// how a batch of reservations is made
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum BatchMode {
+    /// all reservations are made, or none of them if any fails
+    Atomic = 0,
+    /// every reservation that can be made is made, failed ones are skipped
+    BestEffort = 1,
+}
+impl BatchMode {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            BatchMode::Atomic => "BATCH_MODE_ATOMIC",
+            BatchMode::BestEffort => "BATCH_MODE_BEST_EFFORT",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "BATCH_MODE_ATOMIC" => Some(Self::Atomic),
+            "BATCH_MODE_BEST_EFFORT" => Some(Self::BestEffort),
+            _ => None,
+        }
+    }
+}
 /// Generated client implementations.
 pub mod reservation_service_client {
     #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
@@ -327,7 +855,53 @@ pub mod reservation_service_client {
                 .insert(GrpcMethod::new("reservation.ReservationService", "reserve"));
             self.inner.unary(req, path, codec).await
         }
-        /// confirm a pending reservation, if reservation is not pending, do nothing
+        /// make several reservations in one go, see BatchMode
+        pub async fn reserve_batch(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ReserveBatchRequest>,
+        ) -> std::result::Result<tonic::Response<super::ReserveBatchResponse>, tonic::Status>
+        {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/reservation.ReservationService/reserve_batch",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new(
+                "reservation.ReservationService",
+                "reserve_batch",
+            ));
+            self.inner.unary(req, path, codec).await
+        }
+        /// make the occurrences of a recurring reservation in one go
+        pub async fn reserve_recurring(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ReserveRecurringRequest>,
+        ) -> std::result::Result<tonic::Response<super::ReserveRecurringResponse>, tonic::Status>
+        {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/reservation.ReservationService/reserve_recurring",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new(
+                "reservation.ReservationService",
+                "reserve_recurring",
+            ));
+            self.inner.unary(req, path, codec).await
+        }
+        /// confirm a pending reservation, fails if the reservation is not pending
         pub async fn confirm(
             &mut self,
             request: impl tonic::IntoRequest<super::ConfirmRequest>,
@@ -346,7 +920,7 @@ pub mod reservation_service_client {
                 .insert(GrpcMethod::new("reservation.ReservationService", "confirm"));
             self.inner.unary(req, path, codec).await
         }
-        /// update the reservation note
+        /// update the reservation fields listed in the field mask
         pub async fn update(
             &mut self,
             request: impl tonic::IntoRequest<super::UpdateRequest>,
@@ -407,7 +981,7 @@ pub mod reservation_service_client {
             &mut self,
             request: impl tonic::IntoRequest<super::QueryRequest>,
         ) -> std::result::Result<
-            tonic::Response<tonic::codec::Streaming<super::Reservation>>,
+            tonic::Response<tonic::codec::Streaming<super::QueryResponse>>,
             tonic::Status,
         > {
             self.inner.ready().await.map_err(|e| {
@@ -424,14 +998,34 @@ pub mod reservation_service_client {
                 .insert(GrpcMethod::new("reservation.ReservationService", "query"));
             self.inner.server_streaming(req, path, codec).await
         }
-        /// another system could monitor newly added/confirmed/cancelled reservations
-        pub async fn listen(
+        /// find the free slots of a resource within a window
+        pub async fn find_availability(
             &mut self,
-            request: impl tonic::IntoRequest<super::ListenRequest>,
-        ) -> std::result::Result<
-            tonic::Response<tonic::codec::Streaming<super::Reservation>>,
-            tonic::Status,
-        > {
+            request: impl tonic::IntoRequest<super::FindAvailabilityRequest>,
+        ) -> std::result::Result<tonic::Response<super::FindAvailabilityResponse>, tonic::Status>
+        {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/reservation.ReservationService/find_availability",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new(
+                "reservation.ReservationService",
+                "find_availability",
+            ));
+            self.inner.unary(req, path, codec).await
+        }
+        /// filter reservations page by page, using the cursors in FilterPager
+        pub async fn filter(
+            &mut self,
+            request: impl tonic::IntoRequest<super::FilterRequest>,
+        ) -> std::result::Result<tonic::Response<super::FilterResponse>, tonic::Status> {
             self.inner.ready().await.map_err(|e| {
                 tonic::Status::new(
                     tonic::Code::Unknown,
@@ -440,11 +1034,148 @@ pub mod reservation_service_client {
             })?;
             let codec = tonic::codec::ProstCodec::default();
             let path =
-                http::uri::PathAndQuery::from_static("/reservation.ReservationService/listen");
+                http::uri::PathAndQuery::from_static("/reservation.ReservationService/filter");
             let mut req = request.into_request();
             req.extensions_mut()
-                .insert(GrpcMethod::new("reservation.ReservationService", "listen"));
-            self.inner.server_streaming(req, path, codec).await
+                .insert(GrpcMethod::new("reservation.ReservationService", "filter"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// another system could monitor newly added/confirmed/cancelled reservations
+        pub async fn listen(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ListenRequest>,
+        ) -> std::result::Result<
+            tonic::Response<tonic::codec::Streaming<super::ListenResponse>>,
+            tonic::Status,
+        > {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path =
+                http::uri::PathAndQuery::from_static("/reservation.ReservationService/listen");
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("reservation.ReservationService", "listen"));
+            self.inner.server_streaming(req, path, codec).await
+        }
+        /// add a resource to the catalog
+        pub async fn create_resource(
+            &mut self,
+            request: impl tonic::IntoRequest<super::CreateResourceRequest>,
+        ) -> std::result::Result<tonic::Response<super::CreateResourceResponse>, tonic::Status>
+        {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/reservation.ReservationService/create_resource",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new(
+                "reservation.ReservationService",
+                "create_resource",
+            ));
+            self.inner.unary(req, path, codec).await
+        }
+        /// get a resource by id
+        pub async fn get_resource(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetResourceRequest>,
+        ) -> std::result::Result<tonic::Response<super::GetResourceResponse>, tonic::Status>
+        {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/reservation.ReservationService/get_resource",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new(
+                "reservation.ReservationService",
+                "get_resource",
+            ));
+            self.inner.unary(req, path, codec).await
+        }
+        /// list resources, optionally of a given kind
+        pub async fn list_resources(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ListResourcesRequest>,
+        ) -> std::result::Result<tonic::Response<super::ListResourcesResponse>, tonic::Status>
+        {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/reservation.ReservationService/list_resources",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new(
+                "reservation.ReservationService",
+                "list_resources",
+            ));
+            self.inner.unary(req, path, codec).await
+        }
+        /// update the resource fields listed in the field mask
+        pub async fn update_resource(
+            &mut self,
+            request: impl tonic::IntoRequest<super::UpdateResourceRequest>,
+        ) -> std::result::Result<tonic::Response<super::UpdateResourceResponse>, tonic::Status>
+        {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/reservation.ReservationService/update_resource",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new(
+                "reservation.ReservationService",
+                "update_resource",
+            ));
+            self.inner.unary(req, path, codec).await
+        }
+        /// stop a resource from being reserved, existing reservations are kept
+        pub async fn deactivate_resource(
+            &mut self,
+            request: impl tonic::IntoRequest<super::DeactivateResourceRequest>,
+        ) -> std::result::Result<tonic::Response<super::DeactivateResourceResponse>, tonic::Status>
+        {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/reservation.ReservationService/deactivate_resource",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new(
+                "reservation.ReservationService",
+                "deactivate_resource",
+            ));
+            self.inner.unary(req, path, codec).await
         }
     }
 }
@@ -460,12 +1191,22 @@ pub mod reservation_service_server {
             &self,
             request: tonic::Request<super::ReserveRequest>,
         ) -> std::result::Result<tonic::Response<super::ReserveResponse>, tonic::Status>;
-        /// confirm a pending reservation, if reservation is not pending, do nothing
+        /// make several reservations in one go, see BatchMode
+        async fn reserve_batch(
+            &self,
+            request: tonic::Request<super::ReserveBatchRequest>,
+        ) -> std::result::Result<tonic::Response<super::ReserveBatchResponse>, tonic::Status>;
+        /// make the occurrences of a recurring reservation in one go
+        async fn reserve_recurring(
+            &self,
+            request: tonic::Request<super::ReserveRecurringRequest>,
+        ) -> std::result::Result<tonic::Response<super::ReserveRecurringResponse>, tonic::Status>;
+        /// confirm a pending reservation, fails if the reservation is not pending
         async fn confirm(
             &self,
             request: tonic::Request<super::ConfirmRequest>,
         ) -> std::result::Result<tonic::Response<super::ConfirmResponse>, tonic::Status>;
-        /// update the reservation note
+        /// update the reservation fields listed in the field mask
         async fn update(
             &self,
             request: tonic::Request<super::UpdateRequest>,
@@ -482,7 +1223,7 @@ pub mod reservation_service_server {
         ) -> std::result::Result<tonic::Response<super::GetResponse>, tonic::Status>;
         /// Server streaming response type for the query method.
         type queryStream: tonic::codegen::tokio_stream::Stream<
-                Item = std::result::Result<super::Reservation, tonic::Status>,
+                Item = std::result::Result<super::QueryResponse, tonic::Status>,
             > + Send
             + 'static;
         /// query reservation by resource id, user id, status, start time, end time
@@ -490,9 +1231,19 @@ pub mod reservation_service_server {
             &self,
             request: tonic::Request<super::QueryRequest>,
         ) -> std::result::Result<tonic::Response<Self::queryStream>, tonic::Status>;
+        /// find the free slots of a resource within a window
+        async fn find_availability(
+            &self,
+            request: tonic::Request<super::FindAvailabilityRequest>,
+        ) -> std::result::Result<tonic::Response<super::FindAvailabilityResponse>, tonic::Status>;
+        /// filter reservations page by page, using the cursors in FilterPager
+        async fn filter(
+            &self,
+            request: tonic::Request<super::FilterRequest>,
+        ) -> std::result::Result<tonic::Response<super::FilterResponse>, tonic::Status>;
         /// Server streaming response type for the listen method.
         type listenStream: tonic::codegen::tokio_stream::Stream<
-                Item = std::result::Result<super::Reservation, tonic::Status>,
+                Item = std::result::Result<super::ListenResponse, tonic::Status>,
             > + Send
             + 'static;
         /// another system could monitor newly added/confirmed/cancelled reservations
@@ -500,6 +1251,31 @@ pub mod reservation_service_server {
             &self,
             request: tonic::Request<super::ListenRequest>,
         ) -> std::result::Result<tonic::Response<Self::listenStream>, tonic::Status>;
+        /// add a resource to the catalog
+        async fn create_resource(
+            &self,
+            request: tonic::Request<super::CreateResourceRequest>,
+        ) -> std::result::Result<tonic::Response<super::CreateResourceResponse>, tonic::Status>;
+        /// get a resource by id
+        async fn get_resource(
+            &self,
+            request: tonic::Request<super::GetResourceRequest>,
+        ) -> std::result::Result<tonic::Response<super::GetResourceResponse>, tonic::Status>;
+        /// list resources, optionally of a given kind
+        async fn list_resources(
+            &self,
+            request: tonic::Request<super::ListResourcesRequest>,
+        ) -> std::result::Result<tonic::Response<super::ListResourcesResponse>, tonic::Status>;
+        /// update the resource fields listed in the field mask
+        async fn update_resource(
+            &self,
+            request: tonic::Request<super::UpdateResourceRequest>,
+        ) -> std::result::Result<tonic::Response<super::UpdateResourceResponse>, tonic::Status>;
+        /// stop a resource from being reserved, existing reservations are kept
+        async fn deactivate_resource(
+            &self,
+            request: tonic::Request<super::DeactivateResourceRequest>,
+        ) -> std::result::Result<tonic::Response<super::DeactivateResourceResponse>, tonic::Status>;
     }
     /// Reservation service
     #[derive(Debug)]
@@ -618,6 +1394,92 @@ pub mod reservation_service_server {
                     };
                     Box::pin(fut)
                 }
+                "/reservation.ReservationService/reserve_batch" => {
+                    #[allow(non_camel_case_types)]
+                    struct reserve_batchSvc<T: ReservationService>(pub Arc<T>);
+                    impl<T: ReservationService>
+                        tonic::server::UnaryService<super::ReserveBatchRequest>
+                        for reserve_batchSvc<T>
+                    {
+                        type Response = super::ReserveBatchResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ReserveBatchRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as ReservationService>::reserve_batch(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = reserve_batchSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/reservation.ReservationService/reserve_recurring" => {
+                    #[allow(non_camel_case_types)]
+                    struct reserve_recurringSvc<T: ReservationService>(pub Arc<T>);
+                    impl<T: ReservationService>
+                        tonic::server::UnaryService<super::ReserveRecurringRequest>
+                        for reserve_recurringSvc<T>
+                    {
+                        type Response = super::ReserveRecurringResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ReserveRecurringRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as ReservationService>::reserve_recurring(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = reserve_recurringSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 "/reservation.ReservationService/confirm" => {
                     #[allow(non_camel_case_types)]
                     struct confirmSvc<T: ReservationService>(pub Arc<T>);
@@ -784,7 +1646,7 @@ pub mod reservation_service_server {
                     impl<T: ReservationService>
                         tonic::server::ServerStreamingService<super::QueryRequest> for querySvc<T>
                     {
-                        type Response = super::Reservation;
+                        type Response = super::QueryResponse;
                         type ResponseStream = T::queryStream;
                         type Future =
                             BoxFuture<tonic::Response<Self::ResponseStream>, tonic::Status>;
@@ -822,6 +1684,89 @@ pub mod reservation_service_server {
                     };
                     Box::pin(fut)
                 }
+                "/reservation.ReservationService/find_availability" => {
+                    #[allow(non_camel_case_types)]
+                    struct find_availabilitySvc<T: ReservationService>(pub Arc<T>);
+                    impl<T: ReservationService>
+                        tonic::server::UnaryService<super::FindAvailabilityRequest>
+                        for find_availabilitySvc<T>
+                    {
+                        type Response = super::FindAvailabilityResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::FindAvailabilityRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as ReservationService>::find_availability(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = find_availabilitySvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/reservation.ReservationService/filter" => {
+                    #[allow(non_camel_case_types)]
+                    struct filterSvc<T: ReservationService>(pub Arc<T>);
+                    impl<T: ReservationService> tonic::server::UnaryService<super::FilterRequest> for filterSvc<T> {
+                        type Response = super::FilterResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::FilterRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as ReservationService>::filter(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = filterSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 "/reservation.ReservationService/listen" => {
                     #[allow(non_camel_case_types)]
                     struct listenSvc<T: ReservationService>(pub Arc<T>);
@@ -829,7 +1774,7 @@ pub mod reservation_service_server {
                         tonic::server::ServerStreamingService<super::ListenRequest>
                         for listenSvc<T>
                     {
-                        type Response = super::Reservation;
+                        type Response = super::ListenResponse;
                         type ResponseStream = T::listenStream;
                         type Future =
                             BoxFuture<tonic::Response<Self::ResponseStream>, tonic::Status>;
@@ -867,6 +1812,222 @@ pub mod reservation_service_server {
                     };
                     Box::pin(fut)
                 }
+                "/reservation.ReservationService/create_resource" => {
+                    #[allow(non_camel_case_types)]
+                    struct create_resourceSvc<T: ReservationService>(pub Arc<T>);
+                    impl<T: ReservationService>
+                        tonic::server::UnaryService<super::CreateResourceRequest>
+                        for create_resourceSvc<T>
+                    {
+                        type Response = super::CreateResourceResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::CreateResourceRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as ReservationService>::create_resource(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = create_resourceSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/reservation.ReservationService/get_resource" => {
+                    #[allow(non_camel_case_types)]
+                    struct get_resourceSvc<T: ReservationService>(pub Arc<T>);
+                    impl<T: ReservationService>
+                        tonic::server::UnaryService<super::GetResourceRequest>
+                        for get_resourceSvc<T>
+                    {
+                        type Response = super::GetResourceResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetResourceRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as ReservationService>::get_resource(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = get_resourceSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/reservation.ReservationService/list_resources" => {
+                    #[allow(non_camel_case_types)]
+                    struct list_resourcesSvc<T: ReservationService>(pub Arc<T>);
+                    impl<T: ReservationService>
+                        tonic::server::UnaryService<super::ListResourcesRequest>
+                        for list_resourcesSvc<T>
+                    {
+                        type Response = super::ListResourcesResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ListResourcesRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as ReservationService>::list_resources(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = list_resourcesSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/reservation.ReservationService/update_resource" => {
+                    #[allow(non_camel_case_types)]
+                    struct update_resourceSvc<T: ReservationService>(pub Arc<T>);
+                    impl<T: ReservationService>
+                        tonic::server::UnaryService<super::UpdateResourceRequest>
+                        for update_resourceSvc<T>
+                    {
+                        type Response = super::UpdateResourceResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::UpdateResourceRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as ReservationService>::update_resource(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = update_resourceSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/reservation.ReservationService/deactivate_resource" => {
+                    #[allow(non_camel_case_types)]
+                    struct deactivate_resourceSvc<T: ReservationService>(pub Arc<T>);
+                    impl<T: ReservationService>
+                        tonic::server::UnaryService<super::DeactivateResourceRequest>
+                        for deactivate_resourceSvc<T>
+                    {
+                        type Response = super::DeactivateResourceResponse;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::DeactivateResourceRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as ReservationService>::deactivate_resource(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = deactivate_resourceSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 _ => Box::pin(async move {
                     Ok(http::Response::builder()
                         .status(200)
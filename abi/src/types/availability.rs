@@ -0,0 +1,90 @@
+use std::ops::Range;
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::{
+    utils::{convert_to_duration, convert_to_timestamp, convert_to_utc_time},
+    Error, FindAvailabilityRequest, TimeSlot, Validator,
+};
+
+use super::validate_range;
+
+impl FindAvailabilityRequest {
+    pub fn get_window(&self) -> Result<Range<DateTime<Utc>>, Error> {
+        self.validate()?;
+        let start = convert_to_utc_time(self.start.clone().unwrap());
+        let end = convert_to_utc_time(self.end.clone().unwrap());
+        Ok(start..end)
+    }
+
+    /// Shortest slot wanted, zero if any slot will do
+    pub fn get_min_duration(&self) -> Duration {
+        to_duration(self.min_duration.as_ref())
+    }
+
+    /// Step slot bounds are rounded to, zero if they are not rounded
+    pub fn get_granularity(&self) -> Duration {
+        to_duration(self.granularity.as_ref())
+    }
+}
+
+impl Validator for FindAvailabilityRequest {
+    fn validate(&self) -> Result<(), Error> {
+        if self.resource_id.is_empty() {
+            return Err(Error::InvalidResourceId(self.resource_id.clone()));
+        }
+        let out_of_range = |d: &Option<prost_types::Duration>| matches!(d, Some(d) if convert_to_duration(d).is_none());
+        if out_of_range(&self.min_duration) || out_of_range(&self.granularity) {
+            return Err(Error::InvalidTime);
+        }
+        validate_range(self.start.as_ref(), self.end.as_ref())
+    }
+}
+
+impl TimeSlot {
+    pub fn new(start: DateTime<Utc>, end: DateTime<Utc>) -> Self {
+        Self {
+            start: Some(convert_to_timestamp(start)),
+            end: Some(convert_to_timestamp(end)),
+        }
+    }
+}
+
+// negative durations are treated as not set
+fn to_duration(duration: Option<&prost_types::Duration>) -> Duration {
+    let duration = duration
+        .and_then(convert_to_duration)
+        .unwrap_or_else(Duration::zero);
+    duration.max(Duration::zero())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn out_of_range_durations_should_be_rejected() {
+        let mut request = FindAvailabilityRequest {
+            resource_id: "ocean-view-room-713".to_string(),
+            start: Some(convert_to_timestamp(
+                "2022-12-26T00:00:00Z".parse().unwrap(),
+            )),
+            end: Some(convert_to_timestamp(
+                "2022-12-27T00:00:00Z".parse().unwrap(),
+            )),
+            min_duration: Some(prost_types::Duration {
+                seconds: i64::MIN,
+                nanos: 0,
+            }),
+            ..Default::default()
+        };
+        assert_eq!(request.get_window(), Err(Error::InvalidTime));
+
+        request.min_duration = None;
+        request.granularity = Some(prost_types::Duration {
+            seconds: i64::MAX,
+            nanos: 0,
+        });
+        assert_eq!(request.get_window(), Err(Error::InvalidTime));
+    }
+}
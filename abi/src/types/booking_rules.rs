@@ -0,0 +1,307 @@
+use std::ops::Range;
+
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use sqlx::{
+    postgres::{types::PgInterval, PgRow},
+    Row,
+};
+
+use crate::{utils::*, BookingRules, Error, Validator};
+
+impl BookingRules {
+    pub fn get_min_duration(&self) -> Option<Duration> {
+        self.min_duration.as_ref().and_then(convert_to_duration)
+    }
+
+    pub fn get_max_duration(&self) -> Option<Duration> {
+        self.max_duration.as_ref().and_then(convert_to_duration)
+    }
+
+    pub fn get_min_lead_time(&self) -> Option<Duration> {
+        self.min_lead_time.as_ref().and_then(convert_to_duration)
+    }
+
+    pub fn get_max_horizon(&self) -> Option<Duration> {
+        self.max_horizon.as_ref().and_then(convert_to_duration)
+    }
+
+    pub fn get_slot_alignment(&self) -> Option<Duration> {
+        self.slot_alignment.as_ref().and_then(convert_to_duration)
+    }
+
+    pub fn get_pre_buffer(&self) -> Option<Duration> {
+        self.pre_buffer.as_ref().and_then(convert_to_duration)
+    }
+
+    pub fn get_post_buffer(&self) -> Option<Duration> {
+        self.post_buffer.as_ref().and_then(convert_to_duration)
+    }
+
+    /// Check a reservation window requested at `now` against the rules. `local` is the
+    /// same window in the time zone of the resource, which slots are aligned in. The
+    /// buffers are left to conflict detection
+    pub fn check(
+        &self,
+        window: &Range<DateTime<Utc>>,
+        local: &Range<NaiveDateTime>,
+        now: DateTime<Utc>,
+    ) -> Result<(), Error> {
+        let duration = window.end - window.start;
+        if let Some(min) = self.get_min_duration().filter(|min| duration < *min) {
+            return Err(Error::ReservationTooShort {
+                min: min.num_seconds(),
+            });
+        }
+        if let Some(max) = self.get_max_duration().filter(|max| duration > *max) {
+            return Err(Error::ReservationTooLong {
+                max: max.num_seconds(),
+            });
+        }
+
+        let lead_time = window.start - now;
+        if let Some(min) = self.get_min_lead_time().filter(|min| lead_time < *min) {
+            return Err(Error::LeadTimeTooShort {
+                min: min.num_seconds(),
+            });
+        }
+        if let Some(max) = self.get_max_horizon().filter(|max| lead_time > *max) {
+            return Err(Error::BeyondBookingHorizon {
+                max: max.num_seconds(),
+            });
+        }
+
+        if let Some(alignment) = self.get_slot_alignment() {
+            let step = alignment.num_microseconds().unwrap_or(i64::MAX);
+            let aligned = |t: NaiveDateTime| t.and_utc().timestamp_micros() % step == 0;
+            if !aligned(local.start) || !aligned(local.end) {
+                return Err(Error::MisalignedReservation {
+                    alignment: alignment.num_seconds(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Rules stored along with a resource, `None` if no rule is set
+    pub(crate) fn from_row(row: &PgRow) -> Result<Option<Self>, sqlx::Error> {
+        let get = |column: &str| -> Result<Option<prost_types::Duration>, sqlx::Error> {
+            let interval: Option<PgInterval> = row.try_get(column)?;
+            Ok(interval.map(|i| convert_to_pb_duration(convert_interval(i))))
+        };
+        let rules = Self {
+            min_duration: get("min_duration")?,
+            max_duration: get("max_duration")?,
+            min_lead_time: get("min_lead_time")?,
+            max_horizon: get("max_horizon")?,
+            slot_alignment: get("slot_alignment")?,
+            pre_buffer: get("pre_buffer")?,
+            post_buffer: get("post_buffer")?,
+        };
+
+        Ok((rules != Self::default()).then_some(rules))
+    }
+}
+
+impl Validator for BookingRules {
+    fn validate(&self) -> Result<(), Error> {
+        let raw = [
+            ("min_duration", &self.min_duration),
+            ("max_duration", &self.max_duration),
+            ("min_lead_time", &self.min_lead_time),
+            ("max_horizon", &self.max_horizon),
+            ("slot_alignment", &self.slot_alignment),
+            ("pre_buffer", &self.pre_buffer),
+            ("post_buffer", &self.post_buffer),
+        ];
+        for (name, rule) in raw {
+            if matches!(rule, Some(rule) if convert_to_duration(rule).is_none()) {
+                return Err(Error::InvalidBookingRules(format!(
+                    "{} is out of range",
+                    name
+                )));
+            }
+        }
+
+        let rules = [
+            ("min_duration", self.get_min_duration()),
+            ("max_duration", self.get_max_duration()),
+            ("min_lead_time", self.get_min_lead_time()),
+            ("max_horizon", self.get_max_horizon()),
+            ("slot_alignment", self.get_slot_alignment()),
+            ("pre_buffer", self.get_pre_buffer()),
+            ("post_buffer", self.get_post_buffer()),
+        ];
+        for (name, rule) in rules {
+            if matches!(rule, Some(rule) if rule < Duration::zero()) {
+                return Err(Error::InvalidBookingRules(format!(
+                    "{} must not be negative",
+                    name
+                )));
+            }
+        }
+
+        if matches!(
+            (self.get_min_duration(), self.get_max_duration()),
+            (Some(min), Some(max)) if min > max
+        ) {
+            return Err(Error::InvalidBookingRules(
+                "min_duration is longer than max_duration".to_string(),
+            ));
+        }
+        if matches!(self.get_slot_alignment(), Some(alignment) if alignment.is_zero()) {
+            return Err(Error::InvalidBookingRules(
+                "slot_alignment must be positive".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn minutes(m: i64) -> Option<prost_types::Duration> {
+        Some(convert_to_pb_duration(Duration::minutes(m)))
+    }
+
+    fn window(start: &str, end: &str) -> Range<DateTime<Utc>> {
+        start.parse().unwrap()..end.parse().unwrap()
+    }
+
+    // the window in a resource on UTC
+    fn utc(window: &Range<DateTime<Utc>>) -> Range<NaiveDateTime> {
+        window.start.naive_utc()..window.end.naive_utc()
+    }
+
+    fn now() -> DateTime<Utc> {
+        "2022-12-25T08:00:00Z".parse().unwrap()
+    }
+
+    #[test]
+    fn check_should_enforce_duration() {
+        let rules = BookingRules {
+            min_duration: minutes(30),
+            max_duration: minutes(120),
+            ..Default::default()
+        };
+        let ok = window("2022-12-26T10:00:00Z", "2022-12-26T11:00:00Z");
+        assert!(rules.check(&ok, &utc(&ok), now()).is_ok());
+
+        let short = window("2022-12-26T10:00:00Z", "2022-12-26T10:15:00Z");
+        assert_eq!(
+            rules.check(&short, &utc(&short), now()),
+            Err(Error::ReservationTooShort { min: 1800 })
+        );
+
+        let long = window("2022-12-26T10:00:00Z", "2022-12-26T12:15:00Z");
+        assert_eq!(
+            rules.check(&long, &utc(&long), now()),
+            Err(Error::ReservationTooLong { max: 7200 })
+        );
+    }
+
+    #[test]
+    fn check_should_enforce_lead_time_and_horizon() {
+        let rules = BookingRules {
+            min_lead_time: minutes(60),
+            max_horizon: minutes(7 * 24 * 60),
+            ..Default::default()
+        };
+        let soon = window("2022-12-25T08:30:00Z", "2022-12-25T09:30:00Z");
+        assert_eq!(
+            rules.check(&soon, &utc(&soon), now()),
+            Err(Error::LeadTimeTooShort { min: 3600 })
+        );
+
+        let far = window("2023-01-02T08:30:00Z", "2023-01-02T09:30:00Z");
+        assert_eq!(
+            rules.check(&far, &utc(&far), now()),
+            Err(Error::BeyondBookingHorizon { max: 604800 })
+        );
+    }
+
+    #[test]
+    fn check_should_enforce_slot_alignment() {
+        let rules = BookingRules {
+            slot_alignment: minutes(15),
+            ..Default::default()
+        };
+        let aligned = window("2022-12-26T10:15:00Z", "2022-12-26T11:45:00Z");
+        assert!(rules.check(&aligned, &utc(&aligned), now()).is_ok());
+
+        let misaligned = window("2022-12-26T10:15:00Z", "2022-12-26T11:50:00Z");
+        assert_eq!(
+            rules.check(&misaligned, &utc(&misaligned), now()),
+            Err(Error::MisalignedReservation { alignment: 900 })
+        );
+    }
+
+    #[test]
+    fn check_should_align_slots_in_local_time() {
+        let rules = BookingRules {
+            slot_alignment: minutes(60),
+            ..Default::default()
+        };
+        let at = |s: &str| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap();
+        // 10:00 to 11:00 in Asia/Kolkata, UTC+05:30
+        let aligned = window("2022-12-26T04:30:00Z", "2022-12-26T05:30:00Z");
+        let local = at("2022-12-26 10:00")..at("2022-12-26 11:00");
+        assert!(rules.check(&aligned, &local, now()).is_ok());
+
+        let misaligned = window("2022-12-26T04:00:00Z", "2022-12-26T05:00:00Z");
+        let local = at("2022-12-26 09:30")..at("2022-12-26 10:30");
+        assert_eq!(
+            rules.check(&misaligned, &local, now()),
+            Err(Error::MisalignedReservation { alignment: 3600 })
+        );
+    }
+
+    #[test]
+    fn invalid_rules_should_be_rejected() {
+        let rules = BookingRules {
+            min_duration: minutes(60),
+            max_duration: minutes(30),
+            ..Default::default()
+        };
+        assert_eq!(
+            rules.validate(),
+            Err(Error::InvalidBookingRules(
+                "min_duration is longer than max_duration".to_string()
+            ))
+        );
+
+        let rules = BookingRules {
+            post_buffer: minutes(-5),
+            ..Default::default()
+        };
+        assert_eq!(
+            rules.validate(),
+            Err(Error::InvalidBookingRules(
+                "post_buffer must not be negative".to_string()
+            ))
+        );
+
+        let rules = BookingRules {
+            slot_alignment: minutes(0),
+            ..Default::default()
+        };
+        assert!(rules.validate().is_err());
+
+        let rules = BookingRules {
+            max_horizon: Some(prost_types::Duration {
+                seconds: i64::MAX,
+                nanos: 0,
+            }),
+            ..Default::default()
+        };
+        assert_eq!(
+            rules.validate(),
+            Err(Error::InvalidBookingRules(
+                "max_horizon is out of range".to_string()
+            ))
+        );
+    }
+}
@@ -0,0 +1,31 @@
+use sqlx::{postgres::PgRow, types::Uuid, FromRow, Row};
+
+use crate::{ListenResponse, Reservation, ReservationUpdateType, RsvpUpdateType};
+
+/// Build a ListenResponse from a `rsvp.reservation_changes` row left joined with
+/// `rsvp.reservations`. If the reservation no longer exists (e.g. it was deleted),
+/// only its id is populated.
+impl FromRow<'_, PgRow> for ListenResponse {
+    fn from_row(row: &PgRow) -> Result<Self, sqlx::Error> {
+        let change_id: i64 = row.try_get("change_id")?;
+        let op: RsvpUpdateType = row.try_get("op")?;
+        let id: Option<Uuid> = row.try_get("id")?;
+
+        let reservation = match id {
+            Some(_) => Reservation::from_row(row)?,
+            None => {
+                let id: Uuid = row.try_get("reservation_id")?;
+                Reservation {
+                    id: id.to_string(),
+                    ..Default::default()
+                }
+            }
+        };
+
+        Ok(Self {
+            op: ReservationUpdateType::from(op) as i32,
+            reservation: Some(reservation),
+            change_id,
+        })
+    }
+}
@@ -4,11 +4,29 @@ use chrono::{DateTime, Utc};
 use prost_types::Timestamp;
 use sqlx::postgres::types::PgRange;
 
-use crate::{utils::convert_to_utc_time, Error};
+use crate::{utils::convert_to_utc_time, Error, ReservationStatus};
 
+mod availability;
+mod booking_rules;
+mod listen_response;
+mod query_mode;
+mod recurrence;
 mod reservation;
+mod reservation_filter;
 mod reservation_query;
 mod reservation_status;
+mod reservation_update_type;
+mod reserve_batch_result;
+mod resource;
+mod update_request;
+
+pub use recurrence::RecurrenceRule;
+pub use reservation_filter::{CursorDirection, FilterCursor};
+pub use resource::ResourceField;
+pub use update_request::UpdateField;
+
+const DEFAULT_PAGE_SIZE: i64 = 10;
+const MAX_PAGE_SIZE: i64 = 100;
 
 pub fn validate_range(start: Option<&Timestamp>, end: Option<&Timestamp>) -> Result<(), Error> {
     if start.is_none() || end.is_none() {
@@ -22,12 +40,106 @@ pub fn validate_range(start: Option<&Timestamp>, end: Option<&Timestamp>) -> Res
     Ok(())
 }
 
+/// Build a `[start, end)` range, a missing or zero timestamp leaves that side unbounded
 pub fn get_timespan(start: Option<&Timestamp>, end: Option<&Timestamp>) -> PgRange<DateTime<Utc>> {
-    let start = convert_to_utc_time(start.unwrap().clone());
-    let end = convert_to_utc_time(end.unwrap().clone());
+    let start = match bounded(start) {
+        Some(start) => Bound::Included(convert_to_utc_time(start.clone())),
+        None => Bound::Unbounded,
+    };
+    let end = match bounded(end) {
+        Some(end) => Bound::Excluded(convert_to_utc_time(end.clone())),
+        None => Bound::Unbounded,
+    };
+
+    PgRange { start, end }
+}
+
+/// Validate a window whose bounds are optional, it must not be inverted if both are set
+pub fn validate_open_range(
+    start: Option<&Timestamp>,
+    end: Option<&Timestamp>,
+) -> Result<(), Error> {
+    match (bounded(start), bounded(end)) {
+        (Some(start), Some(end)) => validate_range(Some(start), Some(end)),
+        _ => Ok(()),
+    }
+}
+
+fn bounded(ts: Option<&Timestamp>) -> Option<&Timestamp> {
+    ts.filter(|ts| ts.seconds != 0 || ts.nanos != 0)
+}
+
+/// Collect the statuses to match from a single status and a status set, UNKNOWN is
+/// ignored. An empty result means reservations in any status match.
+pub fn get_statuses(status: i32, statuses: &[i32]) -> Vec<String> {
+    let mut names: Vec<String> = std::iter::once(status)
+        .chain(statuses.iter().copied())
+        .filter_map(|s| ReservationStatus::try_from(s).ok())
+        .filter(|s| *s != ReservationStatus::Unknown)
+        .map(|s| s.to_string())
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// Page size used for a query, the default if unset and clamped to
+/// `DEFAULT_PAGE_SIZE..=MAX_PAGE_SIZE` otherwise
+pub fn get_page_size(page_size: i32) -> i64 {
+    if page_size == 0 {
+        return DEFAULT_PAGE_SIZE;
+    }
+    (page_size as i64).clamp(DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ts(s: &str) -> Timestamp {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn get_timespan_should_leave_missing_bounds_unbounded() {
+        let start = ts("2022-12-25T22:00:00Z");
+        let zero = Timestamp::default();
+
+        let range = get_timespan(Some(&start), None);
+        assert_eq!(range.start, Bound::Included(convert_to_utc_time(start)));
+        assert_eq!(range.end, Bound::Unbounded);
+
+        let range = get_timespan(Some(&zero), None);
+        assert_eq!(range.start, Bound::Unbounded);
+        assert_eq!(range.end, Bound::Unbounded);
+    }
+
+    #[test]
+    fn validate_open_range_should_reject_inverted_window() {
+        let start = ts("2022-12-25T22:00:00Z");
+        let end = ts("2022-12-28T19:00:00Z");
+
+        assert!(validate_open_range(None, None).is_ok());
+        assert!(validate_open_range(Some(&start), None).is_ok());
+        assert!(validate_open_range(None, Some(&end)).is_ok());
+        assert!(validate_open_range(Some(&start), Some(&end)).is_ok());
+        assert_eq!(
+            validate_open_range(Some(&end), Some(&start)),
+            Err(Error::InvalidTime)
+        );
+    }
+
+    #[test]
+    fn get_statuses_should_ignore_unknown() {
+        let unknown = ReservationStatus::Unknown as i32;
+        let pending = ReservationStatus::Pending as i32;
+        let confirmed = ReservationStatus::Confirmed as i32;
 
-    PgRange {
-        start: Bound::Included(start),
-        end: Bound::Excluded(end),
+        assert!(get_statuses(unknown, &[]).is_empty());
+        assert_eq!(get_statuses(pending, &[]), vec!["pending"]);
+        assert_eq!(
+            get_statuses(unknown, &[confirmed, pending, pending]),
+            vec!["confirmed", "pending"]
+        );
     }
 }
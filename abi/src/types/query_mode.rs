@@ -0,0 +1,14 @@
+use std::fmt;
+
+use crate::QueryMode;
+
+impl fmt::Display for QueryMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QueryMode::Contained => write!(f, "contained"),
+            QueryMode::Overlapping => write!(f, "overlapping"),
+            QueryMode::StartsWithin => write!(f, "starts_within"),
+            QueryMode::EndsWithin => write!(f, "ends_within"),
+        }
+    }
+}
@@ -0,0 +1,399 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Datelike, Duration, Months, NaiveDate, NaiveDateTime, Utc, Weekday};
+
+use crate::Error;
+
+/// Most occurrences a rule may expand to
+const MAX_OCCURRENCES: usize = 500;
+/// Most periods looked at while expanding, bounds rules that rarely or never match
+const MAX_PERIODS: u32 = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Until {
+    Local(NaiveDateTime),
+    Utc(DateTime<Utc>),
+}
+
+/// The subset of an RFC 5545 recurrence rule supported for recurring reservations:
+/// FREQ (DAILY, WEEKLY or MONTHLY), INTERVAL, COUNT, UNTIL and BYDAY (weekly only)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecurrenceRule {
+    freq: Frequency,
+    interval: u32,
+    count: Option<u32>,
+    until: Option<Until>,
+    by_day: Vec<Weekday>,
+}
+
+impl RecurrenceRule {
+    /// Local start times of the occurrences, the first period starting at `start`. Weekly
+    /// rules with BYDAY only occur on the listed days, so `start` itself is left out if it
+    /// falls on another day. UNTIL is compared with the local start times, so a UTC one
+    /// must be given in local time with `with_local_until` first
+    pub fn expand(&self, start: NaiveDateTime) -> Result<Vec<NaiveDateTime>, Error> {
+        let until = match self.until {
+            Some(Until::Utc(_)) => {
+                return Err(Error::InvalidRecurrenceRule(
+                    "UNTIL in UTC must be converted to local time".to_string(),
+                ))
+            }
+            Some(Until::Local(until)) => Some(until),
+            None => None,
+        };
+
+        let out_of_range = || {
+            Error::InvalidRecurrenceRule("occurrences out of range, INTERVAL too large".to_string())
+        };
+        let mut occurrences = Vec::new();
+        for period in 0..MAX_PERIODS {
+            let offset = period.checked_mul(self.interval).ok_or_else(out_of_range)?;
+            for occurrence in self.period(start, offset).ok_or_else(out_of_range)? {
+                if until.is_some_and(|until| occurrence > until) {
+                    return Self::non_empty(occurrences);
+                }
+                if occurrences.len() == MAX_OCCURRENCES {
+                    return Err(Error::InvalidRecurrenceRule(format!(
+                        "more than {} occurrences",
+                        MAX_OCCURRENCES
+                    )));
+                }
+                occurrences.push(occurrence);
+                if self
+                    .count
+                    .is_some_and(|count| occurrences.len() == count as usize)
+                {
+                    return Ok(occurrences);
+                }
+            }
+        }
+
+        Self::non_empty(occurrences)
+    }
+
+    /// UNTIL if it was given in UTC, with a trailing Z
+    pub fn utc_until(&self) -> Option<DateTime<Utc>> {
+        match self.until {
+            Some(Until::Utc(until)) => Some(until),
+            _ => None,
+        }
+    }
+
+    /// The rule with UNTIL replaced by the local time given
+    pub fn with_local_until(mut self, until: NaiveDateTime) -> Self {
+        self.until = Some(Until::Local(until));
+        self
+    }
+
+    // occurrences within the period `offset` periods after the one of `start`, in order.
+    // `None` if the period is out of the range of dates
+    fn period(&self, start: NaiveDateTime, offset: u32) -> Option<Vec<NaiveDateTime>> {
+        match self.freq {
+            Frequency::Daily => Some(vec![
+                start.checked_add_signed(Duration::days(offset as i64))?
+            ]),
+            Frequency::Weekly if self.by_day.is_empty() => Some(vec![
+                start.checked_add_signed(Duration::weeks(offset as i64))?
+            ]),
+            Frequency::Weekly => {
+                let monday = start
+                    .date()
+                    .checked_sub_signed(Duration::days(
+                        start.weekday().num_days_from_monday() as i64
+                    ))?
+                    .checked_add_signed(Duration::weeks(offset as i64))?;
+                let mut occurrences = Vec::with_capacity(self.by_day.len());
+                for day in &self.by_day {
+                    let date = monday
+                        .checked_add_signed(Duration::days(day.num_days_from_monday() as i64))?;
+                    let occurrence = date.and_time(start.time());
+                    if occurrence >= start {
+                        occurrences.push(occurrence);
+                    }
+                }
+                Some(occurrences)
+            }
+            // months without the day of the month of `start` are skipped, as RFC 5545 does
+            Frequency::Monthly => {
+                let first = start
+                    .date()
+                    .with_day(1)?
+                    .checked_add_months(Months::new(offset))?;
+                Some(
+                    NaiveDate::from_ymd_opt(first.year(), first.month(), start.day())
+                        .map(|date| date.and_time(start.time()))
+                        .into_iter()
+                        .collect(),
+                )
+            }
+        }
+    }
+
+    fn non_empty(occurrences: Vec<NaiveDateTime>) -> Result<Vec<NaiveDateTime>, Error> {
+        if occurrences.is_empty() {
+            return Err(Error::InvalidRecurrenceRule(
+                "the rule has no occurrence".to_string(),
+            ));
+        }
+        Ok(occurrences)
+    }
+}
+
+impl FromStr for RecurrenceRule {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |msg: String| Error::InvalidRecurrenceRule(msg);
+        let rule = s.trim();
+        let rule = rule.strip_prefix("RRULE:").unwrap_or(rule);
+
+        let mut freq = None;
+        let mut interval = 1;
+        let mut count = None;
+        let mut until = None;
+        let mut by_day = Vec::new();
+        for part in rule.split(';').filter(|part| !part.is_empty()) {
+            let Some((name, value)) = part.split_once('=') else {
+                return Err(invalid(format!("malformed part {}", part)));
+            };
+            match name.to_ascii_uppercase().as_str() {
+                "FREQ" => {
+                    freq = Some(match value.to_ascii_uppercase().as_str() {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        _ => return Err(invalid(format!("unsupported FREQ {}", value))),
+                    })
+                }
+                "INTERVAL" => interval = parse_positive("INTERVAL", value)?,
+                "COUNT" => count = Some(parse_positive("COUNT", value)?),
+                "UNTIL" => until = Some(parse_until(value)?),
+                "BYDAY" => {
+                    by_day = value
+                        .split(',')
+                        .map(parse_weekday)
+                        .collect::<Result<_, _>>()?;
+                }
+                _ => return Err(invalid(format!("unsupported part {}", name))),
+            }
+        }
+
+        let Some(freq) = freq else {
+            return Err(invalid("FREQ is required".to_string()));
+        };
+        match (count, until) {
+            (None, None) => return Err(invalid("COUNT or UNTIL is required".to_string())),
+            (Some(_), Some(_)) => {
+                return Err(invalid("COUNT and UNTIL must not be both set".to_string()))
+            }
+            _ => {}
+        }
+        if !by_day.is_empty() && freq != Frequency::Weekly {
+            return Err(invalid(
+                "BYDAY is only supported with FREQ=WEEKLY".to_string(),
+            ));
+        }
+        by_day.sort_by_key(|day: &Weekday| day.num_days_from_monday());
+        by_day.dedup();
+
+        Ok(Self {
+            freq,
+            interval,
+            count,
+            until,
+            by_day,
+        })
+    }
+}
+
+fn parse_positive(name: &str, value: &str) -> Result<u32, Error> {
+    match value.parse() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err(Error::InvalidRecurrenceRule(format!(
+            "{} must be a positive integer",
+            name
+        ))),
+    }
+}
+
+// UNTIL is either a date, which includes the whole day, or a date-time, in UTC if it ends
+// with Z and in local time otherwise
+fn parse_until(value: &str) -> Result<Until, Error> {
+    let invalid = || Error::InvalidRecurrenceRule(format!("invalid UNTIL {}", value));
+    if value.len() == 8 {
+        let date = NaiveDate::parse_from_str(value, "%Y%m%d").map_err(|_| invalid())?;
+        return date
+            .and_hms_opt(23, 59, 59)
+            .map(Until::Local)
+            .ok_or_else(invalid);
+    }
+    match value.strip_suffix('Z') {
+        Some(utc) => NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S")
+            .map(|until| Until::Utc(until.and_utc())),
+        None => NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").map(Until::Local),
+    }
+    .map_err(|_| invalid())
+}
+
+fn parse_weekday(value: &str) -> Result<Weekday, Error> {
+    match value.to_ascii_uppercase().as_str() {
+        "MO" => Ok(Weekday::Mon),
+        "TU" => Ok(Weekday::Tue),
+        "WE" => Ok(Weekday::Wed),
+        "TH" => Ok(Weekday::Thu),
+        "FR" => Ok(Weekday::Fri),
+        "SA" => Ok(Weekday::Sat),
+        "SU" => Ok(Weekday::Sun),
+        _ => Err(Error::InvalidRecurrenceRule(format!(
+            "unsupported BYDAY {}",
+            value
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn expand(rule: &str, start: &str) -> Result<Vec<NaiveDateTime>, Error> {
+        rule.parse::<RecurrenceRule>()?.expand(at(start))
+    }
+
+    #[test]
+    fn weekly_rule_should_expand_on_listed_days() {
+        let occurrences =
+            expand("RRULE:FREQ=WEEKLY;BYDAY=TU;COUNT=12", "2022-12-27 10:00").unwrap();
+        assert_eq!(occurrences.len(), 12);
+        assert_eq!(occurrences[0], at("2022-12-27 10:00"));
+        assert_eq!(occurrences[11], at("2023-03-14 10:00"));
+        assert!(occurrences.iter().all(|o| o.weekday() == Weekday::Tue));
+
+        // starting on a Monday, the first occurrence is the next listed day
+        let occurrences = expand(
+            "FREQ=WEEKLY;INTERVAL=2;BYDAY=FR,WE;COUNT=3",
+            "2022-12-26 09:00",
+        )
+        .unwrap();
+        assert_eq!(
+            occurrences,
+            vec![
+                at("2022-12-28 09:00"),
+                at("2022-12-30 09:00"),
+                at("2023-01-11 09:00")
+            ]
+        );
+    }
+
+    #[test]
+    fn daily_rule_should_stop_at_until() {
+        let occurrences =
+            expand("FREQ=DAILY;INTERVAL=2;UNTIL=20230102", "2022-12-26 18:00").unwrap();
+        assert_eq!(
+            occurrences,
+            vec![
+                at("2022-12-26 18:00"),
+                at("2022-12-28 18:00"),
+                at("2022-12-30 18:00"),
+                at("2023-01-01 18:00")
+            ]
+        );
+
+        let occurrences = expand("FREQ=DAILY;UNTIL=20221227T170000", "2022-12-26 18:00").unwrap();
+        assert_eq!(occurrences, vec![at("2022-12-26 18:00")]);
+    }
+
+    #[test]
+    fn utc_until_should_be_converted_before_expanding() {
+        let rule: RecurrenceRule = "FREQ=DAILY;UNTIL=20221227T170000Z".parse().unwrap();
+        assert_eq!(
+            rule.utc_until(),
+            Some("2022-12-27T17:00:00Z".parse().unwrap())
+        );
+        assert_eq!(
+            rule.expand(at("2022-12-26 18:00")).unwrap_err(),
+            Error::InvalidRecurrenceRule(
+                "UNTIL in UTC must be converted to local time".to_string()
+            )
+        );
+
+        // 17:00 UTC is 01:00 the next day at UTC+8, after the second occurrence
+        let rule = rule.with_local_until(at("2022-12-28 01:00"));
+        assert_eq!(rule.utc_until(), None);
+        assert_eq!(
+            rule.expand(at("2022-12-26 18:00")).unwrap(),
+            vec![at("2022-12-26 18:00"), at("2022-12-27 18:00")]
+        );
+    }
+
+    #[test]
+    fn monthly_rule_should_skip_months_without_the_day() {
+        let occurrences = expand("FREQ=MONTHLY;COUNT=4", "2023-01-31 12:00").unwrap();
+        assert_eq!(
+            occurrences,
+            vec![
+                at("2023-01-31 12:00"),
+                at("2023-03-31 12:00"),
+                at("2023-05-31 12:00"),
+                at("2023-07-31 12:00")
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_rules_should_be_rejected() {
+        let err = |rule: &str| rule.parse::<RecurrenceRule>().unwrap_err();
+        assert_eq!(
+            err("FREQ=WEEKLY;BYDAY=TU"),
+            Error::InvalidRecurrenceRule("COUNT or UNTIL is required".to_string())
+        );
+        assert_eq!(
+            err("FREQ=YEARLY;COUNT=2"),
+            Error::InvalidRecurrenceRule("unsupported FREQ YEARLY".to_string())
+        );
+        assert_eq!(
+            err("FREQ=MONTHLY;BYDAY=1MO;COUNT=2"),
+            Error::InvalidRecurrenceRule("unsupported BYDAY 1MO".to_string())
+        );
+        assert_eq!(
+            err("FREQ=DAILY;COUNT=0"),
+            Error::InvalidRecurrenceRule("COUNT must be a positive integer".to_string())
+        );
+        assert_eq!(
+            err("FREQ=DAILY;BYMONTH=1;COUNT=2"),
+            Error::InvalidRecurrenceRule("unsupported part BYMONTH".to_string())
+        );
+
+        assert_eq!(
+            expand("FREQ=DAILY;COUNT=501", "2022-12-26 18:00").unwrap_err(),
+            Error::InvalidRecurrenceRule("more than 500 occurrences".to_string())
+        );
+        for rule in [
+            "FREQ=DAILY;INTERVAL=100000000;COUNT=2",
+            "FREQ=WEEKLY;INTERVAL=4294967295;COUNT=2",
+            "FREQ=WEEKLY;INTERVAL=100000000;BYDAY=MO;COUNT=3",
+            "FREQ=MONTHLY;INTERVAL=4000000000;COUNT=2",
+        ] {
+            assert_eq!(
+                expand(rule, "2022-12-26 18:00").unwrap_err(),
+                Error::InvalidRecurrenceRule(
+                    "occurrences out of range, INTERVAL too large".to_string()
+                )
+            );
+        }
+        assert_eq!(
+            expand("FREQ=DAILY;UNTIL=20221225", "2022-12-26 18:00").unwrap_err(),
+            Error::InvalidRecurrenceRule("the rule has no occurrence".to_string())
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use std::ops::Bound;
+use std::ops::{Bound, Range};
 
 use sqlx::{
     postgres::{types::PgRange, PgRow},
@@ -29,6 +29,31 @@ impl Reservation {
             start: Some(convert_to_timestamp(start.with_timezone(&Utc))),
             end: Some(convert_to_timestamp(end.with_timezone(&Utc))),
             note: note.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Hold ttl in seconds, `None` if the hold never expires
+    pub fn get_hold_ttl(&self) -> Option<f64> {
+        self.hold_ttl
+            .as_ref()
+            .map(|ttl| ttl.seconds as f64 + ttl.nanos as f64 / 1e9)
+    }
+
+    /// Start and end of the reservation, both are required
+    pub fn get_window(&self) -> Result<Range<DateTime<Utc>>, Error> {
+        validate_range(self.start.as_ref(), self.end.as_ref())?;
+        let start = convert_to_utc_time(self.start.clone().unwrap());
+        let end = convert_to_utc_time(self.end.clone().unwrap());
+        Ok(start..end)
+    }
+
+    /// The same reservation over another window, e.g. an occurrence of a recurring one
+    pub fn with_window(&self, window: &Range<DateTime<Utc>>) -> Self {
+        Self {
+            start: Some(convert_to_timestamp(window.start)),
+            end: Some(convert_to_timestamp(window.end)),
+            ..self.clone()
         }
     }
 
@@ -49,6 +74,10 @@ impl Validator for Reservation {
 
         validate_range(self.start.as_ref(), self.end.as_ref())?;
 
+        if matches!(self.get_hold_ttl(), Some(ttl) if ttl <= 0.0) {
+            return Err(Error::InvalidHoldTtl);
+        }
+
         Ok(())
     }
 }
@@ -64,6 +93,8 @@ impl FromRow<'_, PgRow> for Reservation {
         let end = range.end.unwrap();
 
         let status: RsvpStatus = row.get("status");
+        let expires_at: Option<DateTime<Utc>> = row.get("expires_at");
+        let series_id: Option<Uuid> = row.get("series_id");
 
         Ok(Self {
             id: id.to_string(),
@@ -73,6 +104,10 @@ impl FromRow<'_, PgRow> for Reservation {
             start: Some(convert_to_timestamp(start)),
             end: Some(convert_to_timestamp(end)),
             note: row.get("note"),
+            hold_ttl: None,
+            expires_at: expires_at.map(convert_to_timestamp),
+            version: row.get("version"),
+            series_id: series_id.map(|id| id.to_string()).unwrap_or_default(),
         })
     }
 }
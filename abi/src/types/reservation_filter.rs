@@ -0,0 +1,208 @@
+use std::{fmt, str::FromStr};
+
+use chrono::{DateTime, Utc};
+use sqlx::{postgres::types::PgRange, types::Uuid};
+
+use crate::{
+    utils::convert_to_utc_time, Error, FilterPager, Reservation, ReservationFilter, Validator,
+};
+
+use super::{get_page_size, get_statuses, get_timespan, validate_open_range};
+
+/// Direction of a cursor relative to the row it points to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CursorDirection {
+    After,
+    Before,
+}
+
+/// Position in the `(lower(timespan), id)` ordering of reservations. It is handed
+/// out to clients as an opaque hex string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilterCursor {
+    pub direction: CursorDirection,
+    pub start: DateTime<Utc>,
+    pub id: Uuid,
+}
+
+impl FilterCursor {
+    fn from_reservation(direction: CursorDirection, rsvp: &Reservation) -> Result<Self, Error> {
+        let start = rsvp.start.clone().ok_or(Error::InvalidTime)?;
+        let id =
+            Uuid::parse_str(&rsvp.id).map_err(|_| Error::InvalidReservationId(rsvp.id.clone()))?;
+        Ok(Self {
+            direction,
+            start: convert_to_utc_time(start),
+            id,
+        })
+    }
+}
+
+impl fmt::Display for FilterCursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let direction: u8 = match self.direction {
+            CursorDirection::After => 0,
+            CursorDirection::Before => 1,
+        };
+        write!(f, "{:02x}", direction)?;
+        for b in self.start.timestamp_micros().to_be_bytes() {
+            write!(f, "{:02x}", b)?;
+        }
+        for b in self.id.as_bytes() {
+            write!(f, "{:02x}", b)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for FilterCursor {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || Error::InvalidCursor(s.to_string());
+        if s.len() != 50 || !s.is_ascii() {
+            return Err(err());
+        }
+        let bytes = (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| err())?;
+
+        let direction = match bytes[0] {
+            0 => CursorDirection::After,
+            1 => CursorDirection::Before,
+            _ => return Err(err()),
+        };
+        let micros = i64::from_be_bytes(bytes[1..9].try_into().unwrap());
+        let start = DateTime::<Utc>::from_timestamp_micros(micros).ok_or_else(err)?;
+        let id = Uuid::from_slice(&bytes[9..]).map_err(|_| err())?;
+
+        Ok(Self {
+            direction,
+            start,
+            id,
+        })
+    }
+}
+
+impl ReservationFilter {
+    /// Decode the cursor, `None` means starting from the first page
+    pub fn get_cursor(&self) -> Result<Option<FilterCursor>, Error> {
+        if self.cursor.is_empty() {
+            return Ok(None);
+        }
+        self.cursor.parse().map(Some)
+    }
+
+    pub fn get_statuses(&self) -> Vec<String> {
+        get_statuses(self.status, &self.statuses)
+    }
+
+    pub fn get_timespan(&self) -> PgRange<DateTime<Utc>> {
+        get_timespan(self.start.as_ref(), self.end.as_ref())
+    }
+
+    /// Page size used for the query, between 10 and 100
+    pub fn get_page_size(&self) -> i64 {
+        get_page_size(self.page_size)
+    }
+}
+
+impl Validator for ReservationFilter {
+    fn validate(&self) -> Result<(), Error> {
+        validate_open_range(self.start.as_ref(), self.end.as_ref())
+    }
+}
+
+impl FilterPager {
+    /// Build the pager for rows fetched in scan order (reversed for a `Before` cursor).
+    /// Up to `page_size + 1` rows are expected, the extra one only tells if there
+    /// are more rows and is dropped. Returns the page in the requested order.
+    pub fn paginate(
+        filter: &ReservationFilter,
+        mut rsvps: Vec<Reservation>,
+    ) -> Result<(Self, Vec<Reservation>), Error> {
+        let cursor = filter.get_cursor()?;
+        let page_size = filter.get_page_size() as usize;
+
+        let has_more = rsvps.len() > page_size;
+        rsvps.truncate(page_size);
+
+        let before = matches!(
+            cursor,
+            Some(FilterCursor {
+                direction: CursorDirection::Before,
+                ..
+            })
+        );
+        if before {
+            rsvps.reverse();
+        }
+
+        let (Some(first), Some(last)) = (rsvps.first(), rsvps.last()) else {
+            return Ok((Self::default(), rsvps));
+        };
+
+        // going forward there is a previous page unless we started from the beginning,
+        // going backward there is always a next page
+        let has_prev = if before { has_more } else { cursor.is_some() };
+        let has_next = if before { true } else { has_more };
+
+        let mut pager = Self::default();
+        if has_prev {
+            pager.prev =
+                FilterCursor::from_reservation(CursorDirection::Before, first)?.to_string();
+        }
+        if has_next {
+            pager.next = FilterCursor::from_reservation(CursorDirection::After, last)?.to_string();
+        }
+
+        Ok((pager, rsvps))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cursor() -> FilterCursor {
+        FilterCursor {
+            direction: CursorDirection::Before,
+            start: "2022-12-25T22:00:00Z".parse().unwrap(),
+            id: Uuid::parse_str("7c1b2b6e-2a4f-4a37-9f0e-6c2d1c5e8a10").unwrap(),
+        }
+    }
+
+    #[test]
+    fn cursor_should_round_trip() {
+        let encoded = cursor().to_string();
+        assert_eq!(encoded.len(), 50);
+        assert_eq!(encoded.parse::<FilterCursor>().unwrap(), cursor());
+    }
+
+    #[test]
+    fn invalid_cursor_should_be_rejected() {
+        let mut encoded = cursor().to_string();
+        encoded.replace_range(0..2, "07");
+        assert_eq!(
+            encoded.parse::<FilterCursor>().unwrap_err(),
+            Error::InvalidCursor(encoded.clone())
+        );
+        assert!("not-a-cursor".parse::<FilterCursor>().is_err());
+    }
+
+    #[test]
+    fn page_size_should_be_clamped() {
+        let mut filter = ReservationFilter::default();
+        assert_eq!(filter.get_page_size(), 10);
+        filter.page_size = 20;
+        assert_eq!(filter.get_page_size(), 20);
+        filter.page_size = 1000;
+        assert_eq!(filter.get_page_size(), 100);
+        filter.page_size = 5;
+        assert_eq!(filter.get_page_size(), 10);
+        filter.page_size = -1;
+        assert_eq!(filter.get_page_size(), 10);
+    }
+}
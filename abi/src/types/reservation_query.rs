@@ -0,0 +1,80 @@
+use chrono::{DateTime, Utc};
+use sqlx::postgres::types::PgRange;
+
+use crate::{Error, QueryMode, QueryPager, ReservationQuery, Validator};
+
+use super::{get_page_size, get_statuses, get_timespan, validate_open_range};
+
+impl ReservationQuery {
+    pub fn get_timespan(&self) -> PgRange<DateTime<Utc>> {
+        get_timespan(self.start.as_ref(), self.end.as_ref())
+    }
+
+    pub fn get_statuses(&self) -> Vec<String> {
+        get_statuses(self.status, &self.statuses)
+    }
+
+    pub fn get_mode(&self) -> QueryMode {
+        QueryMode::try_from(self.mode).unwrap_or(QueryMode::Contained)
+    }
+
+    /// Page to return, pages start from 1
+    pub fn get_page(&self) -> i64 {
+        self.page.max(1) as i64
+    }
+
+    /// Page size used for the query, between 10 and 100
+    pub fn get_page_size(&self) -> i64 {
+        get_page_size(self.page_size)
+    }
+}
+
+impl Validator for ReservationQuery {
+    fn validate(&self) -> Result<(), Error> {
+        validate_open_range(self.start.as_ref(), self.end.as_ref())
+    }
+}
+
+impl QueryPager {
+    /// Build the pager of a query given the number of reservations it matches
+    pub fn new(query: &ReservationQuery, total: i64) -> Self {
+        let page = query.get_page();
+        let page_size = query.get_page_size();
+        Self {
+            total,
+            page: page as i32,
+            page_size: page_size as i32,
+            has_next: page * page_size < total,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pager_should_normalize_page_and_size() {
+        let query = ReservationQuery {
+            page: 0,
+            page_size: 1000,
+            ..Default::default()
+        };
+        let pager = QueryPager::new(&query, 10);
+        assert_eq!(pager.page, 1);
+        assert_eq!(pager.page_size, 100);
+        assert!(!pager.has_next);
+    }
+
+    #[test]
+    fn pager_should_tell_if_there_is_a_next_page() {
+        let query = ReservationQuery {
+            page: 2,
+            page_size: 10,
+            ..Default::default()
+        };
+        assert!(QueryPager::new(&query, 21).has_next);
+        assert!(!QueryPager::new(&query, 20).has_next);
+        assert!(!QueryPager::new(&query, 0).has_next);
+    }
+}
@@ -0,0 +1,101 @@
+use std::fmt;
+
+use crate::{Error, ReservationStatus, RsvpStatus};
+
+impl From<RsvpStatus> for ReservationStatus {
+    fn from(status: RsvpStatus) -> Self {
+        match status {
+            RsvpStatus::Pending => ReservationStatus::Pending,
+            RsvpStatus::Blocked => ReservationStatus::Blocked,
+            RsvpStatus::Confirmed => ReservationStatus::Confirmed,
+            RsvpStatus::Unknown => ReservationStatus::Unknown,
+            RsvpStatus::Cancelled => ReservationStatus::Cancelled,
+            RsvpStatus::Released => ReservationStatus::Released,
+        }
+    }
+}
+
+impl fmt::Display for ReservationStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReservationStatus::Pending => write!(f, "pending"),
+            ReservationStatus::Blocked => write!(f, "blocked"),
+            ReservationStatus::Confirmed => write!(f, "confirmed"),
+            ReservationStatus::Unknown => write!(f, "unknown"),
+            ReservationStatus::Cancelled => write!(f, "cancelled"),
+            ReservationStatus::Released => write!(f, "released"),
+        }
+    }
+}
+
+impl ReservationStatus {
+    /// Whether a reservation in this status may move to `to`:
+    ///
+    /// - pending -> confirmed | cancelled
+    /// - confirmed -> cancelled
+    /// - blocked -> released
+    ///
+    /// Nothing moves to blocked, so a booking can't be taken out of conflict checks by
+    /// blocking it. Blocks are made by admins as new reservations
+    pub fn can_transition_to(&self, to: ReservationStatus) -> bool {
+        use ReservationStatus::*;
+        matches!(
+            (self, to),
+            (Pending, Confirmed)
+                | (Pending, Cancelled)
+                | (Confirmed, Cancelled)
+                | (Blocked, Released)
+        )
+    }
+
+    /// Move to `to` if the transition is allowed
+    pub fn transition(self, to: ReservationStatus) -> Result<ReservationStatus, Error> {
+        if self.can_transition_to(to) {
+            Ok(to)
+        } else {
+            Err(Error::InvalidStatusTransition { from: self, to })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allowed_transitions_should_work() {
+        use ReservationStatus::*;
+        for (from, to) in [
+            (Pending, Confirmed),
+            (Pending, Cancelled),
+            (Confirmed, Cancelled),
+            (Blocked, Released),
+        ] {
+            assert_eq!(from.transition(to), Ok(to), "{} -> {}", from, to);
+        }
+    }
+
+    #[test]
+    fn other_transitions_should_be_rejected() {
+        use ReservationStatus::*;
+        for (from, to) in [
+            (Confirmed, Pending),
+            (Cancelled, Confirmed),
+            (Cancelled, Cancelled),
+            (Blocked, Blocked),
+            (Blocked, Confirmed),
+            (Released, Confirmed),
+            (Pending, Blocked),
+            (Confirmed, Blocked),
+            (Cancelled, Blocked),
+            (Released, Blocked),
+            (Unknown, Blocked),
+            (Pending, Unknown),
+        ] {
+            assert_eq!(
+                from.transition(to),
+                Err(Error::InvalidStatusTransition { from, to })
+            );
+        }
+    }
+}
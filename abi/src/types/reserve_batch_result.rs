@@ -0,0 +1,22 @@
+use crate::{
+    reserve_batch_result::Result as BatchResult, Error, Reservation, ReservationConflictInfo,
+    ReserveBatchResult,
+};
+
+impl From<Result<Reservation, Error>> for ReserveBatchResult {
+    fn from(result: Result<Reservation, Error>) -> Self {
+        let result = match result {
+            Ok(rsvp) => BatchResult::Reservation(rsvp),
+            Err(Error::ConflictReservation(ReservationConflictInfo::Parsed(info))) => {
+                BatchResult::Conflict((*info).into())
+            }
+            Err(Error::ConflictReservation(ReservationConflictInfo::Capacity(info))) => {
+                BatchResult::Conflict(info.into())
+            }
+            Err(e) => BatchResult::Error(e.to_string()),
+        };
+        Self {
+            result: Some(result),
+        }
+    }
+}
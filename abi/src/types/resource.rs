@@ -0,0 +1,199 @@
+use std::{collections::HashMap, str::FromStr};
+
+use sqlx::{postgres::PgRow, types::Json, FromRow, Row};
+
+use crate::{BookingRules, Error, Resource, UpdateResourceRequest, Validator};
+
+const DEFAULT_TIMEZONE: &str = "UTC";
+const MAX_RESOURCE_ID_LEN: usize = 64;
+
+/// Resource fields that can be changed with an UpdateResourceRequest
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResourceField {
+    Name,
+    Kind,
+    Timezone,
+    Attributes,
+    Active,
+    Rules,
+    Capacity,
+}
+
+impl Resource {
+    /// Booking rules of the resource, none are enforced if not set
+    pub fn get_rules(&self) -> BookingRules {
+        self.rules.clone().unwrap_or_default()
+    }
+
+    /// How many active reservations may overlap, 1 if not set
+    pub fn get_capacity(&self) -> i32 {
+        if self.capacity == 0 {
+            1
+        } else {
+            self.capacity
+        }
+    }
+
+    /// Time zone of the resource, UTC if not set
+    pub fn get_timezone(&self) -> &str {
+        if self.timezone.is_empty() {
+            DEFAULT_TIMEZONE
+        } else {
+            &self.timezone
+        }
+    }
+}
+
+impl Validator for Resource {
+    fn validate(&self) -> Result<(), Error> {
+        if self.id.is_empty() || self.id.len() > MAX_RESOURCE_ID_LEN {
+            return Err(Error::InvalidResourceId(self.id.clone()));
+        }
+
+        if self.name.is_empty() {
+            return Err(Error::InvalidResourceName(self.name.clone()));
+        }
+
+        if self.capacity < 0 {
+            return Err(Error::InvalidCapacity(self.capacity));
+        }
+
+        self.get_rules().validate()
+    }
+}
+
+impl FromRow<'_, PgRow> for Resource {
+    fn from_row(row: &PgRow) -> Result<Self, sqlx::Error> {
+        let attributes: Json<HashMap<String, String>> = row.try_get("attributes")?;
+
+        Ok(Self {
+            id: row.get("id"),
+            name: row.get("name"),
+            kind: row.get("kind"),
+            timezone: row.get("timezone"),
+            attributes: attributes.0,
+            active: row.get("active"),
+            rules: BookingRules::from_row(row)?,
+            capacity: row.get("capacity"),
+        })
+    }
+}
+
+impl FromStr for ResourceField {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "name" => Ok(ResourceField::Name),
+            "kind" => Ok(ResourceField::Kind),
+            "timezone" => Ok(ResourceField::Timezone),
+            "attributes" => Ok(ResourceField::Attributes),
+            "active" => Ok(ResourceField::Active),
+            "rules" => Ok(ResourceField::Rules),
+            "capacity" => Ok(ResourceField::Capacity),
+            _ => Err(Error::InvalidFieldMask(format!("unknown field {}", s))),
+        }
+    }
+}
+
+impl UpdateResourceRequest {
+    /// Fields listed in the field mask, at least one is required
+    pub fn get_fields(&self) -> Result<Vec<ResourceField>, Error> {
+        let paths = self
+            .field_mask
+            .as_ref()
+            .map(|mask| mask.paths.as_slice())
+            .unwrap_or_default();
+        if paths.is_empty() {
+            return Err(Error::InvalidFieldMask("no field to update".to_string()));
+        }
+        paths.iter().map(|path| path.parse()).collect()
+    }
+}
+
+impl Validator for UpdateResourceRequest {
+    fn validate(&self) -> Result<(), Error> {
+        if self.id.is_empty() {
+            return Err(Error::InvalidResourceId(self.id.clone()));
+        }
+        let fields = self.get_fields()?;
+        let resource = self.resource.clone().unwrap_or_default();
+        if fields.contains(&ResourceField::Name) && resource.name.is_empty() {
+            return Err(Error::InvalidResourceName(resource.name));
+        }
+        if fields.contains(&ResourceField::Rules) {
+            resource.get_rules().validate()?;
+        }
+        if fields.contains(&ResourceField::Capacity) && resource.capacity < 0 {
+            return Err(Error::InvalidCapacity(resource.capacity));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use prost_types::FieldMask;
+
+    use super::*;
+
+    fn request(paths: &[&str], name: &str) -> UpdateResourceRequest {
+        UpdateResourceRequest {
+            id: "ocean-view-room-713".to_string(),
+            field_mask: Some(FieldMask {
+                paths: paths.iter().map(|p| p.to_string()).collect(),
+            }),
+            resource: Some(Resource {
+                name: name.to_string(),
+                ..Default::default()
+            }),
+        }
+    }
+
+    #[test]
+    fn resource_should_be_validated() {
+        let resource = Resource {
+            id: "ocean-view-room-713".to_string(),
+            name: "Ocean view room 713".to_string(),
+            ..Default::default()
+        };
+        assert!(resource.validate().is_ok());
+        assert_eq!(resource.get_timezone(), "UTC");
+
+        let err = Resource {
+            id: "x".repeat(65),
+            ..resource.clone()
+        }
+        .validate()
+        .unwrap_err();
+        assert_eq!(err, Error::InvalidResourceId("x".repeat(65)));
+
+        let err = Resource {
+            name: "".to_string(),
+            ..resource
+        }
+        .validate()
+        .unwrap_err();
+        assert_eq!(err, Error::InvalidResourceName("".to_string()));
+    }
+
+    #[test]
+    fn resource_field_mask_should_be_parsed() {
+        let req = request(&["name", "attributes", "active"], "Room 713");
+        assert!(req.validate().is_ok());
+        assert_eq!(
+            req.get_fields().unwrap(),
+            vec![
+                ResourceField::Name,
+                ResourceField::Attributes,
+                ResourceField::Active
+            ]
+        );
+
+        let err = request(&["id"], "").validate().unwrap_err();
+        assert_eq!(err, Error::InvalidFieldMask("unknown field id".to_string()));
+
+        let err = request(&["name"], "").validate().unwrap_err();
+        assert_eq!(err, Error::InvalidResourceName("".to_string()));
+    }
+}
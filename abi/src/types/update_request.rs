@@ -0,0 +1,102 @@
+use std::str::FromStr;
+
+use crate::{Error, UpdateRequest, Validator};
+
+/// Reservation fields that can be changed with an UpdateRequest
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpdateField {
+    Note,
+    UserId,
+}
+
+impl FromStr for UpdateField {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "note" => Ok(UpdateField::Note),
+            "user_id" => Ok(UpdateField::UserId),
+            _ => Err(Error::InvalidFieldMask(format!("unknown field {}", s))),
+        }
+    }
+}
+
+impl UpdateRequest {
+    /// Fields listed in the field mask, at least one is required
+    pub fn get_fields(&self) -> Result<Vec<UpdateField>, Error> {
+        let paths = self
+            .field_mask
+            .as_ref()
+            .map(|mask| mask.paths.as_slice())
+            .unwrap_or_default();
+        if paths.is_empty() {
+            return Err(Error::InvalidFieldMask("no field to update".to_string()));
+        }
+        paths.iter().map(|path| path.parse()).collect()
+    }
+}
+
+impl Validator for UpdateRequest {
+    fn validate(&self) -> Result<(), Error> {
+        let fields = self.get_fields()?;
+        let user_id = self
+            .reservation
+            .as_ref()
+            .map(|rsvp| rsvp.user_id.as_str())
+            .unwrap_or_default();
+        if fields.contains(&UpdateField::UserId) && user_id.is_empty() {
+            return Err(Error::InvalidUserId(user_id.to_string()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use prost_types::FieldMask;
+
+    use super::*;
+    use crate::Reservation;
+
+    fn request(paths: &[&str], user_id: &str) -> UpdateRequest {
+        UpdateRequest {
+            id: "id".to_string(),
+            field_mask: Some(FieldMask {
+                paths: paths.iter().map(|p| p.to_string()).collect(),
+            }),
+            reservation: Some(Reservation {
+                user_id: user_id.to_string(),
+                ..Default::default()
+            }),
+            expected_version: None,
+        }
+    }
+
+    #[test]
+    fn field_mask_should_be_parsed() {
+        let req = request(&["note", "user_id"], "aliceid");
+        assert!(req.validate().is_ok());
+        assert_eq!(
+            req.get_fields().unwrap(),
+            vec![UpdateField::Note, UpdateField::UserId]
+        );
+    }
+
+    #[test]
+    fn invalid_field_mask_should_be_rejected() {
+        let err = request(&[], "").validate().unwrap_err();
+        assert_eq!(
+            err,
+            Error::InvalidFieldMask("no field to update".to_string())
+        );
+
+        let err = request(&["status"], "").validate().unwrap_err();
+        assert_eq!(
+            err,
+            Error::InvalidFieldMask("unknown field status".to_string())
+        );
+
+        let err = request(&["user_id"], "").validate().unwrap_err();
+        assert_eq!(err, Error::InvalidUserId("".to_string()));
+    }
+}
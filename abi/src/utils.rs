@@ -1,5 +1,9 @@
+use chrono::Duration;
 use prost_types::Timestamp;
-use sqlx::types::chrono::{DateTime, Utc};
+use sqlx::{
+    postgres::types::PgInterval,
+    types::chrono::{DateTime, Utc},
+};
 
 pub fn convert_to_utc_time(ts: Timestamp) -> DateTime<Utc> {
     DateTime::<Utc>::from_timestamp(ts.seconds, ts.nanos as _).unwrap()
@@ -11,3 +15,32 @@ pub fn convert_to_timestamp(dt: DateTime<Utc>) -> Timestamp {
         nanos: dt.timestamp_subsec_nanos() as _,
     }
 }
+
+// the range of google.protobuf.Duration, about 10,000 years either way
+const MAX_DURATION_SECONDS: u64 = 315_576_000_000;
+
+/// `None` if the duration is out of the range of google.protobuf.Duration
+pub fn convert_to_duration(duration: &prost_types::Duration) -> Option<Duration> {
+    if duration.seconds.unsigned_abs() > MAX_DURATION_SECONDS {
+        return None;
+    }
+    Some(Duration::seconds(duration.seconds) + Duration::nanoseconds(duration.nanos as i64))
+}
+
+pub fn convert_to_pb_duration(duration: Duration) -> prost_types::Duration {
+    let seconds = duration.num_seconds();
+    let nanos = (duration - Duration::seconds(seconds))
+        .num_nanoseconds()
+        .unwrap_or(0);
+    prost_types::Duration {
+        seconds,
+        nanos: nanos as _,
+    }
+}
+
+// intervals are only ever written from durations, a month is taken as 30 days like
+// postgres' justify_days does
+pub fn convert_interval(interval: PgInterval) -> Duration {
+    Duration::days(interval.months as i64 * 30 + interval.days as i64)
+        + Duration::microseconds(interval.microseconds)
+}
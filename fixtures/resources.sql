@@ -0,0 +1,6 @@
+-- resources reserved by the tests
+INSERT INTO rsvp.resources (id, name, kind) VALUES
+  ('ocean-view-room-713', 'Ocean view room 713', 'room'),
+  ('ocean-view-room-714', 'Ocean view room 714', 'room'),
+  ('ocean-view-room-715', 'Ocean view room 715', 'room'),
+  ('ocean-view-room-716', 'Ocean view room 716', 'room');
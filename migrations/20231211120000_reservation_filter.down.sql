@@ -0,0 +1 @@
+DROP INDEX rsvp.reservations_start_id_idx;
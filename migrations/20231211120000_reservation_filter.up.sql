@@ -0,0 +1,2 @@
+-- keyset pagination orders reservations by start time and id
+CREATE INDEX reservations_start_id_idx ON rsvp.reservations (lower(timespan), id);
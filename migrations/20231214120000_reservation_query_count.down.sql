@@ -0,0 +1,60 @@
+DROP FUNCTION rsvp.query_count;
+
+CREATE OR REPLACE FUNCTION rsvp.query(
+  uid text,
+  rid text,
+  during TSTZRANGE,
+  statuses rsvp.reservation_status[],
+  page integer DEFAULT 1,
+  is_desc bool DEFAULT FALSE,
+  page_size integer DEFAULT 10,
+  mode text DEFAULT 'contained'
+) RETURNS TABLE (LIKE rsvp.reservations) AS $$
+DECLARE
+  _sql text;
+BEGIN
+  IF page_size < 10 OR page_size > 100 THEN
+    page_size := 10;
+  END IF;
+  IF page < 1 THEN
+    page := 1;
+  END IF;
+  IF mode NOT IN ('contained', 'overlapping', 'starts_within', 'ends_within') THEN
+    RAISE EXCEPTION 'invalid query mode: %', mode;
+  END IF;
+  -- format the query based on parameters
+  _sql := format(
+      'SELECT * FROM rsvp.reservations WHERE %s AND %s AND %s ORDER BY lower(timespan) %s LIMIT
+       %s OFFSET %s',
+      CASE mode
+        WHEN 'contained' THEN format('%L::tstzrange @> timespan', during)
+        WHEN 'overlapping' THEN format('%L::tstzrange && timespan', during)
+        WHEN 'starts_within' THEN format('%L::tstzrange @> lower(timespan)', during)
+        -- the end of a reservation is exclusive, so it ends within (lower, upper]
+        WHEN 'ends_within' THEN format(
+          'tstzrange(lower(%1$L::tstzrange), upper(%1$L::tstzrange), ''(]'') @> upper(timespan)',
+          during
+        )
+      END,
+      CASE
+        WHEN statuses IS NULL OR cardinality(statuses) = 0 THEN 'TRUE'
+        ELSE 'status = ANY(' || quote_literal(statuses) || ')'
+      END,
+      CASE
+        WHEN uid IS NULL AND rid IS NULL THEN 'TRUE'
+        WHEN uid IS NULL THEN 'resource_id = ' || quote_literal(rid)
+        WHEN rid IS NULL THEN 'user_id = ' || quote_literal(uid)
+        ELSE 'user_id = ' || quote_literal(uid) || ' AND resource_id = ' || quote_literal(rid)
+      END,
+      CASE
+        WHEN is_desc THEN 'DESC'
+        ELSE 'ASC'
+      END,
+      page_size,
+      (page - 1) * page_size
+  );
+  RETURN QUERY EXECUTE _sql;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP FUNCTION rsvp.query_condition;
@@ -0,0 +1,91 @@
+-- build the WHERE condition shared by rsvp.query and rsvp.query_count
+CREATE OR REPLACE FUNCTION rsvp.query_condition(
+  uid text,
+  rid text,
+  during TSTZRANGE,
+  statuses rsvp.reservation_status[],
+  mode text DEFAULT 'contained'
+) RETURNS text AS $$
+BEGIN
+  IF mode NOT IN ('contained', 'overlapping', 'starts_within', 'ends_within') THEN
+    RAISE EXCEPTION 'invalid query mode: %', mode;
+  END IF;
+  RETURN format(
+      '%s AND %s AND %s',
+      CASE mode
+        WHEN 'contained' THEN format('%L::tstzrange @> timespan', during)
+        WHEN 'overlapping' THEN format('%L::tstzrange && timespan', during)
+        WHEN 'starts_within' THEN format('%L::tstzrange @> lower(timespan)', during)
+        -- the end of a reservation is exclusive, so it ends within (lower, upper]
+        WHEN 'ends_within' THEN format(
+          'tstzrange(lower(%1$L::tstzrange), upper(%1$L::tstzrange), ''(]'') @> upper(timespan)',
+          during
+        )
+      END,
+      CASE
+        WHEN statuses IS NULL OR cardinality(statuses) = 0 THEN 'TRUE'
+        ELSE 'status = ANY(' || quote_literal(statuses) || ')'
+      END,
+      CASE
+        WHEN uid IS NULL AND rid IS NULL THEN 'TRUE'
+        WHEN uid IS NULL THEN 'resource_id = ' || quote_literal(rid)
+        WHEN rid IS NULL THEN 'user_id = ' || quote_literal(uid)
+        ELSE 'user_id = ' || quote_literal(uid) || ' AND resource_id = ' || quote_literal(rid)
+      END
+  );
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION rsvp.query(
+  uid text,
+  rid text,
+  during TSTZRANGE,
+  statuses rsvp.reservation_status[],
+  page integer DEFAULT 1,
+  is_desc bool DEFAULT FALSE,
+  page_size integer DEFAULT 10,
+  mode text DEFAULT 'contained'
+) RETURNS TABLE (LIKE rsvp.reservations) AS $$
+DECLARE
+  _sql text;
+BEGIN
+  IF page_size < 10 OR page_size > 100 THEN
+    page_size := 10;
+  END IF;
+  IF page < 1 THEN
+    page := 1;
+  END IF;
+  -- format the query based on parameters
+  _sql := format(
+      'SELECT * FROM rsvp.reservations WHERE %s ORDER BY lower(timespan) %s LIMIT
+       %s OFFSET %s',
+      rsvp.query_condition(uid, rid, during, statuses, mode),
+      CASE
+        WHEN is_desc THEN 'DESC'
+        ELSE 'ASC'
+      END,
+      page_size,
+      (page - 1) * page_size
+  );
+  RETURN QUERY EXECUTE _sql;
+END;
+$$ LANGUAGE plpgsql;
+
+-- count all reservations matched by rsvp.query, regardless of pagination
+CREATE OR REPLACE FUNCTION rsvp.query_count(
+  uid text,
+  rid text,
+  during TSTZRANGE,
+  statuses rsvp.reservation_status[],
+  mode text DEFAULT 'contained'
+) RETURNS bigint AS $$
+DECLARE
+  _total bigint;
+BEGIN
+  EXECUTE format(
+      'SELECT count(*) FROM rsvp.reservations WHERE %s',
+      rsvp.query_condition(uid, rid, during, statuses, mode)
+  ) INTO _total;
+  RETURN _total;
+END;
+$$ LANGUAGE plpgsql;
@@ -0,0 +1,3 @@
+-- postgres can't drop a value from an enum, 'cancelled' is left in place and
+-- unused once the cancelled rows are moved to 'unknown', which keeps them for history
+UPDATE rsvp.reservations SET status = 'unknown' WHERE status = 'cancelled';
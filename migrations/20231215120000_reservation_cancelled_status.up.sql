@@ -0,0 +1,3 @@
+-- cancelled reservations are kept for history instead of being deleted. The new
+-- value can't be used in the transaction adding it, so it gets its own migration
+ALTER TYPE rsvp.reservation_status ADD VALUE 'cancelled';
@@ -0,0 +1,17 @@
+-- cancelled rows may overlap others, which the constraint restored doesn't allow. They
+-- are left for an operator to sort out rather than deleted
+DO $$
+BEGIN
+  IF EXISTS (
+    SELECT 1 FROM rsvp.reservations a JOIN rsvp.reservations b
+      ON a.resource_id = b.resource_id AND a.id <> b.id AND a.timespan && b.timespan
+    WHERE a.status = 'cancelled'
+  ) THEN
+    RAISE EXCEPTION 'cancelled reservations overlap others, delete them before reverting';
+  END IF;
+END
+$$;
+
+ALTER TABLE rsvp.reservations DROP CONSTRAINT reservation_conflict;
+ALTER TABLE rsvp.reservations ADD CONSTRAINT reservation_conflict EXCLUDE
+  USING gist (resource_id WITH =, timespan WITH &&);
@@ -0,0 +1,4 @@
+-- a cancelled reservation no longer holds its slot
+ALTER TABLE rsvp.reservations DROP CONSTRAINT reservation_conflict;
+ALTER TABLE rsvp.reservations ADD CONSTRAINT reservation_conflict EXCLUDE
+  USING gist (resource_id WITH =, timespan WITH &&) WHERE (status <> 'cancelled');
@@ -0,0 +1,3 @@
+ALTER TABLE rsvp.reservations DROP CONSTRAINT reservation_conflict;
+ALTER TABLE rsvp.reservations ADD CONSTRAINT reservation_conflict EXCLUDE
+  USING gist (resource_id WITH =, timespan WITH &&) WHERE (status <> 'cancelled');
@@ -0,0 +1,5 @@
+-- only pending and confirmed reservations hold their slot, blocked and cancelled
+-- rows may be overlapped
+ALTER TABLE rsvp.reservations DROP CONSTRAINT reservation_conflict;
+ALTER TABLE rsvp.reservations ADD CONSTRAINT reservation_conflict EXCLUDE
+  USING gist (resource_id WITH =, timespan WITH &&) WHERE (status IN ('pending', 'confirmed'));
@@ -0,0 +1,45 @@
+-- pending reservations may be held only until expires_at, after that they are
+-- cancelled by the reaper
+ALTER TABLE rsvp.reservations ADD COLUMN expires_at TIMESTAMPTZ;
+
+CREATE INDEX reservations_expires_at_idx ON rsvp.reservations (expires_at)
+  WHERE status = 'pending';
+
+-- TABLE (LIKE rsvp.reservations) is fixed to the columns at creation time, return
+-- the row type instead so the function follows new columns
+DROP FUNCTION rsvp.query;
+
+CREATE FUNCTION rsvp.query(
+  uid text,
+  rid text,
+  during TSTZRANGE,
+  statuses rsvp.reservation_status[],
+  page integer DEFAULT 1,
+  is_desc bool DEFAULT FALSE,
+  page_size integer DEFAULT 10,
+  mode text DEFAULT 'contained'
+) RETURNS SETOF rsvp.reservations AS $$
+DECLARE
+  _sql text;
+BEGIN
+  IF page_size < 10 OR page_size > 100 THEN
+    page_size := 10;
+  END IF;
+  IF page < 1 THEN
+    page := 1;
+  END IF;
+  -- format the query based on parameters
+  _sql := format(
+      'SELECT * FROM rsvp.reservations WHERE %s ORDER BY lower(timespan) %s LIMIT
+       %s OFFSET %s',
+      rsvp.query_condition(uid, rid, during, statuses, mode),
+      CASE
+        WHEN is_desc THEN 'DESC'
+        ELSE 'ASC'
+      END,
+      page_size,
+      (page - 1) * page_size
+  );
+  RETURN QUERY EXECUTE _sql;
+END;
+$$ LANGUAGE plpgsql;
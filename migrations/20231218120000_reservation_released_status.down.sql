@@ -0,0 +1,3 @@
+-- postgres can't drop a value from an enum, 'released' is left in place and
+-- unused once the released rows are gone
+DELETE FROM rsvp.reservations WHERE status = 'released';
@@ -0,0 +1,2 @@
+-- a blocked slot is released once the block is lifted, the row is kept for history
+ALTER TYPE rsvp.reservation_status ADD VALUE 'released';
@@ -0,0 +1,3 @@
+DROP TRIGGER reservations_version_trigger ON rsvp.reservations;
+DROP FUNCTION rsvp.reservations_version_trigger;
+ALTER TABLE rsvp.reservations DROP COLUMN version;
@@ -0,0 +1,10 @@
+-- responses of reserve / confirm calls made with an idempotency key, so a retried
+-- call gets the original response back instead of running again
+CREATE TABLE rsvp.idempotency_keys (
+  operation VARCHAR(32) NOT NULL,
+  idempotency_key VARCHAR(128) NOT NULL,
+  response BYTEA,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+
+  CONSTRAINT idempotency_keys_pkey PRIMARY KEY (operation, idempotency_key)
+);
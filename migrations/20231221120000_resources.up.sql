@@ -0,0 +1,22 @@
+-- catalog of the resources that can be reserved
+CREATE TABLE rsvp.resources (
+  id VARCHAR(64) NOT NULL,
+  name VARCHAR(128) NOT NULL,
+  kind VARCHAR(64) NOT NULL DEFAULT '',
+  -- IANA time zone name of the resource
+  timezone VARCHAR(64) NOT NULL DEFAULT 'UTC',
+  attributes JSONB NOT NULL DEFAULT '{}',
+  -- inactive resources are kept for their reservations but can't be reserved
+  active BOOLEAN NOT NULL DEFAULT TRUE,
+
+  CONSTRAINT resources_pkey PRIMARY KEY (id)
+);
+
+CREATE INDEX resources_kind_idx ON rsvp.resources (kind);
+
+-- resources reserved so far become catalog entries named after their id
+INSERT INTO rsvp.resources (id, name)
+  SELECT DISTINCT resource_id, resource_id FROM rsvp.reservations;
+
+ALTER TABLE rsvp.reservations ADD CONSTRAINT reservations_resource_id_fkey
+  FOREIGN KEY (resource_id) REFERENCES rsvp.resources (id);
@@ -0,0 +1,7 @@
+ALTER TABLE rsvp.resources
+  DROP COLUMN min_duration,
+  DROP COLUMN max_duration,
+  DROP COLUMN min_lead_time,
+  DROP COLUMN max_horizon,
+  DROP COLUMN slot_alignment,
+  DROP COLUMN buffer_time;
@@ -0,0 +1,13 @@
+-- booking rules of a resource, a NULL rule is not enforced
+ALTER TABLE rsvp.resources
+  -- shortest and longest reservation allowed
+  ADD COLUMN min_duration INTERVAL,
+  ADD COLUMN max_duration INTERVAL,
+  -- how long before its start a reservation must be made at least
+  ADD COLUMN min_lead_time INTERVAL,
+  -- how far ahead a reservation can start at most
+  ADD COLUMN max_horizon INTERVAL,
+  -- reservation bounds must be multiples of this, counted from the unix epoch
+  ADD COLUMN slot_alignment INTERVAL,
+  -- free time to keep between active reservations
+  ADD COLUMN buffer_time INTERVAL;
@@ -0,0 +1,21 @@
+-- reservations overlapping within a shared resource can't be kept apart by the constraint
+-- restored, they are left for an operator to sort out rather than deleted
+DO $$
+BEGIN
+  IF EXISTS (
+    SELECT 1 FROM rsvp.reservations a JOIN rsvp.reservations b
+      ON a.resource_id = b.resource_id AND a.id < b.id AND a.timespan && b.timespan
+    WHERE NOT a.exclusive AND NOT b.exclusive
+      AND a.status IN ('pending', 'confirmed') AND b.status IN ('pending', 'confirmed')
+  ) THEN
+    RAISE EXCEPTION 'active reservations of a shared resource overlap, cancel them before reverting';
+  END IF;
+END
+$$;
+
+ALTER TABLE rsvp.reservations DROP CONSTRAINT reservation_conflict;
+ALTER TABLE rsvp.reservations ADD CONSTRAINT reservation_conflict EXCLUDE
+  USING gist (resource_id WITH =, timespan WITH &&) WHERE (status IN ('pending', 'confirmed'));
+
+ALTER TABLE rsvp.reservations DROP COLUMN exclusive;
+ALTER TABLE rsvp.resources DROP COLUMN capacity;
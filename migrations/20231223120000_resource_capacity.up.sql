@@ -0,0 +1,13 @@
+-- how many active reservations of a resource may overlap at any time
+ALTER TABLE rsvp.resources ADD COLUMN capacity INTEGER NOT NULL DEFAULT 1
+  CONSTRAINT resources_capacity_check CHECK (capacity > 0);
+
+-- the exclusion constraint only covers reservations of resources with a capacity of 1,
+-- those of larger resources are counted when reserving. Kept in sync with the capacity
+-- of the resource
+ALTER TABLE rsvp.reservations ADD COLUMN exclusive BOOLEAN NOT NULL DEFAULT TRUE;
+
+ALTER TABLE rsvp.reservations DROP CONSTRAINT reservation_conflict;
+ALTER TABLE rsvp.reservations ADD CONSTRAINT reservation_conflict EXCLUDE
+  USING gist (resource_id WITH =, timespan WITH &&)
+  WHERE (status IN ('pending', 'confirmed') AND exclusive);
@@ -0,0 +1,17 @@
+ALTER TABLE rsvp.reservations DROP CONSTRAINT reservation_conflict;
+ALTER TABLE rsvp.reservations ADD CONSTRAINT reservation_conflict EXCLUDE
+  USING gist (resource_id WITH =, timespan WITH &&)
+  WHERE (status IN ('pending', 'confirmed') AND exclusive);
+
+ALTER TABLE rsvp.reservations
+  DROP COLUMN effective_timespan,
+  DROP COLUMN pre_buffer,
+  DROP COLUMN post_buffer;
+
+DROP FUNCTION rsvp.pad_timespan;
+
+ALTER TABLE rsvp.resources ADD COLUMN buffer_time INTERVAL;
+UPDATE rsvp.resources SET buffer_time = post_buffer;
+ALTER TABLE rsvp.resources
+  DROP COLUMN pre_buffer,
+  DROP COLUMN post_buffer;
@@ -0,0 +1,31 @@
+-- time kept free before and after the reservations of a resource, e.g. for setup and
+-- cleaning. Replaces buffer_time, which is the same as a post buffer
+ALTER TABLE rsvp.resources
+  ADD COLUMN pre_buffer INTERVAL,
+  ADD COLUMN post_buffer INTERVAL;
+UPDATE rsvp.resources SET post_buffer = buffer_time;
+ALTER TABLE rsvp.resources DROP COLUMN buffer_time;
+
+-- extend a timespan by the buffers. The buffers are turned into plain seconds, so the
+-- result doesn't depend on the time zone and the function is really immutable
+CREATE OR REPLACE FUNCTION rsvp.pad_timespan(timespan TSTZRANGE, pre INTERVAL, post INTERVAL)
+RETURNS TSTZRANGE AS $$
+  SELECT tstzrange(
+    lower(timespan) - make_interval(secs => extract(epoch FROM pre)),
+    upper(timespan) + make_interval(secs => extract(epoch FROM post))
+  )
+$$ LANGUAGE sql IMMUTABLE;
+
+-- buffers of the resource when the reservation was made or moved, existing reservations
+-- have none. timespan stays the window of the customer, effective_timespan is the one
+-- the resource is taken for
+ALTER TABLE rsvp.reservations
+  ADD COLUMN pre_buffer INTERVAL NOT NULL DEFAULT '0',
+  ADD COLUMN post_buffer INTERVAL NOT NULL DEFAULT '0',
+  ADD COLUMN effective_timespan TSTZRANGE NOT NULL
+    GENERATED ALWAYS AS (rsvp.pad_timespan(timespan, pre_buffer, post_buffer)) STORED;
+
+ALTER TABLE rsvp.reservations DROP CONSTRAINT reservation_conflict;
+ALTER TABLE rsvp.reservations ADD CONSTRAINT reservation_conflict EXCLUDE
+  USING gist (resource_id WITH =, effective_timespan WITH &&)
+  WHERE (status IN ('pending', 'confirmed') AND exclusive);
@@ -0,0 +1 @@
+ALTER TABLE rsvp.reservations DROP COLUMN series_id;
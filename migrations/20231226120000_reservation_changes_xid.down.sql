@@ -0,0 +1,22 @@
+CREATE OR REPLACE FUNCTION rsvp.reservations_trigger() RETURNS TRIGGER AS $$
+BEGIN
+  PERFORM pg_advisory_xact_lock('rsvp.reservation_changes'::regclass::oid::bigint);
+  IF TG_OP = 'INSERT' THEN
+    INSERT INTO rsvp.reservation_changes (reservation_id, op) VALUES
+    (NEW.id, 'create');
+  ELSEIF TG_OP = 'UPDATE' THEN
+    IF OLD.status <> NEW.status THEN
+      INSERT INTO rsvp.reservation_changes (reservation_id,op)
+      VALUES (NEW.id, 'update');
+    END IF;
+  ELSEIF TG_OP = 'DELETE' THEN
+    INSERT INTO rsvp.reservation_changes (reservation_id,op)
+    VALUES (OLD.id, 'delete');
+  END IF;
+  -- notify a channel called reservation_update
+  NOTIFY reservation_update;
+  RETURN NULL;
+END
+$$ LANGUAGE plpgsql;
+
+ALTER TABLE rsvp.reservation_changes DROP COLUMN xid;
@@ -0,0 +1,27 @@
+-- change ids are handed out when a change is recorded, not when it commits, so they don't
+-- tell listeners which changes are final. Record the writing transaction instead: once it
+-- is older than every transaction in flight, no change can show up before it anymore
+ALTER TABLE rsvp.reservation_changes ADD COLUMN xid xid8 NOT NULL DEFAULT pg_current_xact_id();
+
+CREATE INDEX reservation_changes_xid_idx ON rsvp.reservation_changes (xid, id);
+
+-- writers no longer take turns to record their changes
+CREATE OR REPLACE FUNCTION rsvp.reservations_trigger() RETURNS TRIGGER AS $$
+BEGIN
+  IF TG_OP = 'INSERT' THEN
+    INSERT INTO rsvp.reservation_changes (reservation_id, op) VALUES
+    (NEW.id, 'create');
+  ELSEIF TG_OP = 'UPDATE' THEN
+    IF OLD.status <> NEW.status THEN
+      INSERT INTO rsvp.reservation_changes (reservation_id,op)
+      VALUES (NEW.id, 'update');
+    END IF;
+  ELSEIF TG_OP = 'DELETE' THEN
+    INSERT INTO rsvp.reservation_changes (reservation_id,op)
+    VALUES (OLD.id, 'delete');
+  END IF;
+  -- notify a channel called reservation_update
+  NOTIFY reservation_update;
+  RETURN NULL;
+END
+$$ LANGUAGE plpgsql;
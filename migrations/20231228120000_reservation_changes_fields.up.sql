@@ -0,0 +1,22 @@
+-- any change a client can see is sent to listeners, e.g. a note or user updated through a
+-- field mask. Columns only used internally, like the version or the buffers, are left out
+CREATE OR REPLACE FUNCTION rsvp.reservations_trigger() RETURNS TRIGGER AS $$
+BEGIN
+  IF TG_OP = 'INSERT' THEN
+    INSERT INTO rsvp.reservation_changes (reservation_id, op) VALUES
+    (NEW.id, 'create');
+  ELSEIF TG_OP = 'UPDATE' THEN
+    IF (OLD.user_id, OLD.status, OLD.resource_id, OLD.timespan, OLD.note, OLD.expires_at, OLD.series_id)
+      IS DISTINCT FROM (NEW.user_id, NEW.status, NEW.resource_id, NEW.timespan, NEW.note, NEW.expires_at, NEW.series_id) THEN
+      INSERT INTO rsvp.reservation_changes (reservation_id,op)
+      VALUES (NEW.id, 'update');
+    END IF;
+  ELSEIF TG_OP = 'DELETE' THEN
+    INSERT INTO rsvp.reservation_changes (reservation_id,op)
+    VALUES (OLD.id, 'delete');
+  END IF;
+  -- notify a channel called reservation_update
+  NOTIFY reservation_update;
+  RETURN NULL;
+END
+$$ LANGUAGE plpgsql;
@@ -0,0 +1,3 @@
+DROP INDEX rsvp.idempotency_keys_created_at_idx;
+
+ALTER TABLE rsvp.idempotency_keys DROP COLUMN request_hash;
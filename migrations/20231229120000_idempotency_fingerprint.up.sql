@@ -0,0 +1,7 @@
+-- a key is tied to the request it was first used with, so reusing it for another request
+-- is rejected instead of returning an unrelated response. Keys claimed before this have
+-- none and match any request
+ALTER TABLE rsvp.idempotency_keys ADD COLUMN request_hash BYTEA;
+
+-- expired keys are purged periodically
+CREATE INDEX idempotency_keys_created_at_idx ON rsvp.idempotency_keys (created_at);
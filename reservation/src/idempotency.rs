@@ -0,0 +1,76 @@
+use std::time::Duration;
+
+use prost::Message;
+use sqlx::{PgConnection, PgPool};
+
+// a key used before the window is claimed again, and a retry within the window waits
+// for the first call to commit or roll back on the primary key
+const CLAIM_SQL: &str = "INSERT INTO rsvp.idempotency_keys (operation, idempotency_key, request_hash) VALUES ($1, $2, sha256($4)) ON CONFLICT (operation, idempotency_key) DO UPDATE SET response = NULL, request_hash = EXCLUDED.request_hash, created_at = now() WHERE rsvp.idempotency_keys.created_at <= now() - make_interval(secs => $3::float8) RETURNING operation";
+
+// the stored response, and whether the key was claimed by the same request
+const RESPONSE_SQL: &str = "SELECT response, COALESCE(request_hash = sha256($3), TRUE) FROM rsvp.idempotency_keys WHERE operation = $1 AND idempotency_key = $2";
+
+const PURGE_SQL: &str =
+    "DELETE FROM rsvp.idempotency_keys WHERE created_at <= now() - make_interval(secs => $1::float8)";
+
+/// Claim `key` for `operation` in the transaction of `conn`. Returns the response
+/// stored by an earlier call within `window`, `None` if the operation should run.
+/// `request` identifies the call, an earlier call with the same key must have made
+/// the same request
+pub(crate) async fn claim(
+    conn: &mut PgConnection,
+    operation: &str,
+    key: &str,
+    request: &[u8],
+    window: Duration,
+) -> Result<Option<abi::Reservation>, abi::Error> {
+    let claimed: Option<String> = sqlx::query_scalar(CLAIM_SQL)
+        .bind(operation)
+        .bind(key)
+        .bind(window.as_secs_f64())
+        .bind(request)
+        .fetch_optional(&mut *conn)
+        .await?;
+    if claimed.is_some() {
+        return Ok(None);
+    }
+
+    let (response, same_request): (Option<Vec<u8>>, bool) = sqlx::query_as(RESPONSE_SQL)
+        .bind(operation)
+        .bind(key)
+        .bind(request)
+        .fetch_one(&mut *conn)
+        .await?;
+    if !same_request {
+        return Err(abi::Error::IdempotencyKeyReused(key.to_string()));
+    }
+    let response = response.ok_or(abi::Error::Unknown)?;
+    let rsvp = abi::Reservation::decode(response.as_slice()).map_err(|_| abi::Error::Unknown)?;
+    Ok(Some(rsvp))
+}
+
+/// Store the response of a claimed key, it becomes visible once the transaction commits
+pub(crate) async fn store(
+    conn: &mut PgConnection,
+    operation: &str,
+    key: &str,
+    rsvp: &abi::Reservation,
+) -> Result<(), abi::Error> {
+    let sql = "UPDATE rsvp.idempotency_keys SET response = $3 WHERE operation = $1 AND idempotency_key = $2";
+    sqlx::query(sql)
+        .bind(operation)
+        .bind(key)
+        .bind(rsvp.encode_to_vec())
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+/// Drop the keys older than `window`, which would be claimed again anyway
+pub(crate) async fn purge(pool: &PgPool, window: Duration) -> Result<u64, abi::Error> {
+    let result = sqlx::query(PURGE_SQL)
+        .bind(window.as_secs_f64())
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected())
+}
@@ -1,32 +1,111 @@
+mod idempotency;
+mod listener;
 mod manager;
+mod reaper;
+mod recurrence;
+mod resource;
 
-use async_trait::async_trait;
+use std::{ops::Range, time::Duration};
 
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::stream::BoxStream;
 use sqlx::PgPool;
 
 pub type ReservationId = String;
 pub type UserId = String;
 pub type ResourceId = String;
+pub type QueryStream = BoxStream<'static, Result<abi::Reservation, abi::Error>>;
+pub type ListenStream = BoxStream<'static, Result<abi::ListenResponse, abi::Error>>;
+pub type Occurrence = (Range<DateTime<Utc>>, Result<abi::Reservation, abi::Error>);
 
 #[derive(Debug)]
 pub struct ReservationManager {
     pool: PgPool,
+    // how long the response of a call made with an idempotency key is replayed
+    idempotency_window: Duration,
+}
+
+#[derive(Debug)]
+pub struct ResourceManager {
+    pool: PgPool,
 }
 
 #[async_trait]
 pub trait Rsvp {
     // make a reservation
     async fn reserve(&self, rsvp: abi::Reservation) -> Result<abi::Reservation, abi::Error>;
-    // change reservation status (if current status is pending, change it to confirmed)
-    async fn change_status(&self, id: ReservationId) -> Result<abi::Reservation, abi::Error>;
+    // make a reservation once per idempotency key, a retry returns the original reservation
+    async fn reserve_idempotent(
+        &self,
+        key: String,
+        rsvp: abi::Reservation,
+    ) -> Result<abi::Reservation, abi::Error>;
+    // make several reservations in one transaction, returning the result of each in order
+    async fn reserve_batch(
+        &self,
+        rsvps: Vec<abi::Reservation>,
+        mode: abi::BatchMode,
+    ) -> Result<Vec<Result<abi::Reservation, abi::Error>>, abi::Error>;
+    // make every occurrence of a recurring reservation in one transaction, see BatchMode. Returns
+    // the series id linking the occurrences made, empty if none was, and the window and
+    // result of each occurrence in order
+    async fn reserve_recurring(
+        &self,
+        rsvp: abi::Reservation,
+        rule: abi::RecurrenceRule,
+        mode: abi::BatchMode,
+    ) -> Result<(String, Vec<Occurrence>), abi::Error>;
+    // confirm reservation, i.e. transition it from pending to confirmed
+    async fn change_status(
+        &self,
+        id: ReservationId,
+        expected_version: Option<i64>,
+    ) -> Result<abi::Reservation, abi::Error>;
+    // confirm reservation once per idempotency key, a retry returns the original reservation
+    async fn change_status_idempotent(
+        &self,
+        key: String,
+        id: ReservationId,
+        expected_version: Option<i64>,
+    ) -> Result<abi::Reservation, abi::Error>;
+    // move reservation to another status, following the transitions allowed by abi::ReservationStatus
+    async fn transition(
+        &self,
+        id: ReservationId,
+        to: abi::ReservationStatus,
+        expected_version: Option<i64>,
+    ) -> Result<abi::Reservation, abi::Error>;
     // update note
     async fn update_note(
         &self,
         id: ReservationId,
         note: String,
+        expected_version: Option<i64>,
     ) -> Result<abi::Reservation, abi::Error>;
+    // move reservation to a new window, and to a new resource if given, keeping it as is on conflict
+    async fn reschedule(
+        &self,
+        id: ReservationId,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        resource_id: Option<ResourceId>,
+        expected_version: Option<i64>,
+    ) -> Result<abi::Reservation, abi::Error>;
+    // cancel reservation, the record is kept with cancelled status and frees its slot
+    async fn cancel(
+        &self,
+        id: ReservationId,
+        expected_version: Option<i64>,
+    ) -> Result<abi::Reservation, abi::Error>;
+    // update the fields listed in the request field mask
+    async fn update(&self, request: abi::UpdateRequest) -> Result<abi::Reservation, abi::Error>;
     // delete reservation
-    async fn delete(&self, id: ReservationId) -> Result<(), abi::Error>;
+    async fn delete(
+        &self,
+        id: ReservationId,
+        expected_version: Option<i64>,
+    ) -> Result<(), abi::Error>;
     // get reservation by id
     async fn get(&self, id: ReservationId) -> Result<abi::Reservation, abi::Error>;
     // query reservations
@@ -34,4 +113,52 @@ pub trait Rsvp {
         &self,
         query: abi::ReservationQuery,
     ) -> Result<Vec<abi::Reservation>, abi::Error>;
+    // query reservations as a stream, rows are fetched from the database as they are consumed
+    async fn query_stream(&self, query: abi::ReservationQuery) -> Result<QueryStream, abi::Error>;
+    // the rows of a query, with a pager counting all reservations matching it and describing
+    // the requested page. Both are read in the same snapshot
+    async fn query_page(
+        &self,
+        query: abi::ReservationQuery,
+    ) -> Result<(abi::QueryPager, QueryStream), abi::Error>;
+    // find the free slots of a resource within the window, rounded inwards to the granularity
+    // and at least min_duration long. A slot is free if it can be reserved, i.e. fewer active
+    // reservations than the capacity of the resource overlap it at any time
+    async fn availability(
+        &self,
+        resource_id: ResourceId,
+        window: Range<DateTime<Utc>>,
+        min_duration: chrono::Duration,
+        granularity: chrono::Duration,
+    ) -> Result<Vec<abi::TimeSlot>, abi::Error>;
+    // filter reservations page by page with a cursor, ordered by start time
+    async fn filter(
+        &self,
+        filter: abi::ReservationFilter,
+    ) -> Result<(abi::FilterPager, Vec<abi::Reservation>), abi::Error>;
+    // cancel pending reservations whose hold expired, returning them
+    async fn expire_holds(&self) -> Result<Vec<abi::Reservation>, abi::Error>;
+    // listen to reservation changes, replaying those after `since` change id if given
+    async fn listen(&self, since: Option<i64>) -> Result<ListenStream, abi::Error>;
+}
+
+#[async_trait]
+pub trait ResourceCatalog {
+    // add a resource to the catalog, it starts out active
+    async fn create(&self, resource: abi::Resource) -> Result<abi::Resource, abi::Error>;
+    // get resource by id
+    async fn get(&self, id: ResourceId) -> Result<abi::Resource, abi::Error>;
+    // list resources ordered by id, of the given kind if not empty
+    async fn list(
+        &self,
+        kind: String,
+        include_inactive: bool,
+    ) -> Result<Vec<abi::Resource>, abi::Error>;
+    // update the fields listed in the request field mask
+    async fn update(
+        &self,
+        request: abi::UpdateResourceRequest,
+    ) -> Result<abi::Resource, abi::Error>;
+    // stop the resource from being reserved, its reservations are kept
+    async fn deactivate(&self, id: ResourceId) -> Result<abi::Resource, abi::Error>;
 }
@@ -0,0 +1,396 @@
+use std::{collections::VecDeque, time::Duration};
+
+use futures::stream;
+use sqlx::{postgres::PgListener, FromRow, PgPool, Row};
+
+use crate::ListenStream;
+
+// channel notified by rsvp.reservations_trigger() on every change
+const CHANNEL: &str = "reservation_update";
+
+// a page of the changes after the cursor, in the order their transactions started. A
+// change is settled once its transaction is older than every one in flight, nothing can be
+// recorded before it from then on
+const CHANGES_SQL: &str = "SELECT c.id::int8 AS change_id, c.xid::text::int8 AS xid, c.xid < pg_snapshot_xmin(pg_current_snapshot()) AS settled, c.reservation_id, c.op, r.* FROM rsvp.reservation_changes c LEFT JOIN rsvp.reservations r ON r.id = c.reservation_id WHERE (c.xid, c.id) > ($1::int8::text::xid8, $2) ORDER BY c.xid, c.id LIMIT $3";
+
+// most changes read at once, a long replay is read page by page as the client keeps up
+const CHANGES_PAGE: i64 = 500;
+
+// cursor of a change id given by a client, from the start if it is unknown
+const CURSOR_SQL: &str =
+    "SELECT COALESCE((SELECT xid::text::int8 FROM rsvp.reservation_changes WHERE id = $1), 0)";
+
+// cursor of the last settled change
+const LATEST_SQL: &str = "SELECT xid::text::int8, id::int8 FROM rsvp.reservation_changes WHERE xid < pg_snapshot_xmin(pg_current_snapshot()) ORDER BY xid DESC, id DESC LIMIT 1";
+
+// how long to wait before looking again at changes held back by a transaction in flight,
+// which may commit without notifying
+const HELD_BACK_RETRY: Duration = Duration::from_millis(200);
+
+/// Turns `reservation_update` notifications into change rows read from
+/// `rsvp.reservation_changes`. Changes are only sent once settled, so the id of any sent
+/// change is a cursor no later change can slip behind.
+struct ChangeFeed {
+    pool: PgPool,
+    listener: PgListener,
+    // transaction id and change id of the last change sent
+    cursor: (i64, i64),
+    // whether unsettled changes were left out by the last fetch
+    held_back: bool,
+    // whether the last fetch read a full page, so more changes may follow it
+    more: bool,
+    pending: VecDeque<abi::ListenResponse>,
+}
+
+impl ChangeFeed {
+    async fn new(pool: PgPool, since: Option<i64>) -> Result<Self, abi::Error> {
+        // subscribe before reading any change, so nothing recorded in between is lost
+        let mut listener = PgListener::connect_with(&pool).await?;
+        listener.listen(CHANNEL).await?;
+
+        let cursor = match since {
+            Some(id) => {
+                let xid = sqlx::query_scalar(CURSOR_SQL)
+                    .bind(id)
+                    .fetch_one(&pool)
+                    .await?;
+                (xid, id)
+            }
+            None => sqlx::query_as(LATEST_SQL)
+                .fetch_optional(&pool)
+                .await?
+                .unwrap_or_default(),
+        };
+
+        let mut feed = Self {
+            pool,
+            listener,
+            cursor,
+            held_back: false,
+            more: false,
+            pending: VecDeque::new(),
+        };
+        // replay what was missed, and what was held back when starting from the latest
+        // change. Live changes are picked up from the notifications
+        feed.fetch_changes().await?;
+        Ok(feed)
+    }
+
+    async fn next(&mut self) -> Result<abi::ListenResponse, abi::Error> {
+        loop {
+            if let Some(change) = self.pending.pop_front() {
+                return Ok(change);
+            }
+            // `None` means the connection was re-established and notifications may
+            // have been missed, in both cases we catch up from the change table
+            if self.more {
+                // the rest of the replay doesn't need a notification
+            } else if self.held_back {
+                let _ = tokio::time::timeout(HELD_BACK_RETRY, self.listener.try_recv()).await;
+            } else {
+                self.listener.try_recv().await?;
+            }
+            self.fetch_changes().await?;
+        }
+    }
+
+    async fn fetch_changes(&mut self) -> Result<(), abi::Error> {
+        let rows = sqlx::query(CHANGES_SQL)
+            .bind(self.cursor.0)
+            .bind(self.cursor.1)
+            .bind(CHANGES_PAGE)
+            .fetch_all(&self.pool)
+            .await?;
+
+        self.more = rows.len() as i64 == CHANGES_PAGE;
+        self.held_back = false;
+        for row in rows {
+            if !row.get::<bool, _>("settled") {
+                self.held_back = true;
+                self.more = false;
+                break;
+            }
+            let change = abi::ListenResponse::from_row(&row)?;
+            self.cursor = (row.get("xid"), change.change_id);
+            self.pending.push_back(change);
+        }
+        Ok(())
+    }
+}
+
+pub(crate) async fn listen(pool: PgPool, since: Option<i64>) -> Result<ListenStream, abi::Error> {
+    let feed = ChangeFeed::new(pool, since).await?;
+
+    let stream = stream::try_unfold(feed, |mut feed| async move {
+        let change = feed.next().await?;
+        Ok(Some((change, feed)))
+    });
+
+    Ok(Box::pin(stream))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use abi::ReservationUpdateType;
+    use futures::StreamExt;
+    use sqlx::{types::Uuid, Pool, Postgres};
+
+    use crate::{ReservationManager, Rsvp};
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn listen_should_stream_changes_in_order(migrated_pool: Pool<Postgres>) {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let mut stream = manager.listen(None).await.unwrap();
+
+        let rsvp = abi::Reservation::new_pending(
+            "tyrid",
+            "ocean-view-room-713",
+            "2022-12-25T15:00:00-0700".parse().unwrap(),
+            "2022-12-28T12:00:00-0700".parse().unwrap(),
+            "hello.",
+        );
+        let rsvp = manager.reserve(rsvp).await.unwrap();
+        manager.change_status(rsvp.id.clone(), None).await.unwrap();
+        manager.delete(rsvp.id.clone(), None).await.unwrap();
+
+        let mut ops = vec![];
+        for _ in 0..3 {
+            let change = tokio::time::timeout(Duration::from_secs(5), stream.next())
+                .await
+                .unwrap()
+                .unwrap()
+                .unwrap();
+            assert_eq!(change.reservation.unwrap().id, rsvp.id);
+            ops.push(ReservationUpdateType::try_from(change.op).unwrap());
+        }
+
+        assert_eq!(
+            ops,
+            vec![
+                ReservationUpdateType::Create,
+                ReservationUpdateType::Update,
+                ReservationUpdateType::Delete
+            ]
+        );
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn listen_should_hold_back_changes_behind_a_transaction_in_flight(
+        migrated_pool: Pool<Postgres>,
+    ) {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let mut stream = manager.listen(None).await.unwrap();
+
+        // the first writer records its change, then the second one commits before it
+        let mut tx = migrated_pool.begin().await.unwrap();
+        let first: Uuid = sqlx::query_scalar("INSERT INTO rsvp.reservations (user_id, resource_id, timespan, note) VALUES ('tyrid', 'ocean-view-room-713', '[2022-12-25, 2022-12-28)', 'hello.') RETURNING id")
+            .fetch_one(&mut *tx)
+            .await
+            .unwrap();
+        let second = abi::Reservation::new_pending(
+            "aliceid",
+            "ocean-view-room-714",
+            "2022-12-25T15:00:00-0700".parse().unwrap(),
+            "2022-12-28T12:00:00-0700".parse().unwrap(),
+            "hello.",
+        );
+        let second = manager.reserve(second).await.unwrap();
+
+        // the change of the second writer waits for the first one
+        let held = tokio::time::timeout(Duration::from_millis(500), stream.next()).await;
+        assert!(held.is_err());
+        tx.commit().await.unwrap();
+
+        let mut ids = vec![];
+        for _ in 0..2 {
+            let change = tokio::time::timeout(Duration::from_secs(5), stream.next())
+                .await
+                .unwrap()
+                .unwrap()
+                .unwrap();
+            ids.push(change.reservation.unwrap().id);
+        }
+        assert_eq!(ids, vec![first.to_string(), second.id]);
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn listen_should_stream_rescheduled_reservations(migrated_pool: Pool<Postgres>) {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let rsvp = abi::Reservation::new_pending(
+            "tyrid",
+            "ocean-view-room-713",
+            "2022-12-25T15:00:00-0700".parse().unwrap(),
+            "2022-12-28T12:00:00-0700".parse().unwrap(),
+            "hello.",
+        );
+        let rsvp = manager.reserve(rsvp).await.unwrap();
+
+        let mut stream = manager.listen(None).await.unwrap();
+        let moved = manager
+            .reschedule(
+                rsvp.id,
+                "2022-12-29T15:00:00Z".parse().unwrap(),
+                "2022-12-30T12:00:00Z".parse().unwrap(),
+                Some("ocean-view-room-714".into()),
+                None,
+            )
+            .await
+            .unwrap();
+
+        let change = tokio::time::timeout(Duration::from_secs(5), stream.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert_eq!(change.op, ReservationUpdateType::Update as i32);
+        assert_eq!(change.reservation.unwrap(), moved);
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn listen_should_stream_field_mask_updates(migrated_pool: Pool<Postgres>) {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let rsvp = abi::Reservation::new_pending(
+            "tyrid",
+            "ocean-view-room-713",
+            "2022-12-25T15:00:00-0700".parse().unwrap(),
+            "2022-12-28T12:00:00-0700".parse().unwrap(),
+            "hello.",
+        );
+        let rsvp = manager.reserve(rsvp).await.unwrap();
+
+        let mut stream = manager.listen(None).await.unwrap();
+        let request = abi::UpdateRequest {
+            id: rsvp.id.clone(),
+            field_mask: Some(prost_types::FieldMask {
+                paths: vec!["note".into()],
+            }),
+            reservation: Some(abi::Reservation {
+                note: "late check-in".into(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let updated = manager.update(request).await.unwrap();
+
+        let change = tokio::time::timeout(Duration::from_secs(5), stream.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert_eq!(change.op, ReservationUpdateType::Update as i32);
+        assert_eq!(change.reservation.unwrap(), updated);
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn listen_should_skip_changes_before_subscribing(migrated_pool: Pool<Postgres>) {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let rsvp = abi::Reservation::new_pending(
+            "tyrid",
+            "ocean-view-room-713",
+            "2022-12-25T15:00:00-0700".parse().unwrap(),
+            "2022-12-28T12:00:00-0700".parse().unwrap(),
+            "hello.",
+        );
+        let rsvp = manager.reserve(rsvp).await.unwrap();
+
+        let mut stream = manager.listen(None).await.unwrap();
+        let rsvp = manager.change_status(rsvp.id, None).await.unwrap();
+
+        let change = tokio::time::timeout(Duration::from_secs(5), stream.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert_eq!(change.op, ReservationUpdateType::Update as i32);
+        assert_eq!(change.reservation.unwrap(), rsvp);
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn listen_since_change_id_should_replay_then_stream_live(migrated_pool: Pool<Postgres>) {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let rsvp1 = abi::Reservation::new_pending(
+            "tyrid",
+            "ocean-view-room-713",
+            "2022-12-25T15:00:00-0700".parse().unwrap(),
+            "2022-12-28T12:00:00-0700".parse().unwrap(),
+            "hello.",
+        );
+        let rsvp2 = abi::Reservation::new_pending(
+            "aliceid",
+            "ocean-view-room-714",
+            "2022-12-25T15:00:00-0700".parse().unwrap(),
+            "2022-12-28T12:00:00-0700".parse().unwrap(),
+            "hello.",
+        );
+
+        // find the cursor of the first change, then disconnect
+        let mut stream = manager.listen(None).await.unwrap();
+        let rsvp1 = manager.reserve(rsvp1).await.unwrap();
+        let first = stream.next().await.unwrap().unwrap();
+        drop(stream);
+
+        // changes made while disconnected
+        manager.change_status(rsvp1.id.clone(), None).await.unwrap();
+        let rsvp2 = manager.reserve(rsvp2).await.unwrap();
+
+        let mut stream = manager.listen(Some(first.change_id)).await.unwrap();
+        manager.delete(rsvp1.id.clone(), None).await.unwrap();
+
+        let mut changes = vec![];
+        for _ in 0..3 {
+            let change = tokio::time::timeout(Duration::from_secs(5), stream.next())
+                .await
+                .unwrap()
+                .unwrap()
+                .unwrap();
+            changes.push(change);
+        }
+
+        let ids: Vec<_> = changes.iter().map(|c| c.change_id).collect();
+        assert_eq!(
+            ids,
+            vec![
+                first.change_id + 1,
+                first.change_id + 2,
+                first.change_id + 3
+            ]
+        );
+
+        let summary: Vec<_> = changes
+            .into_iter()
+            .map(|c| (c.reservation.unwrap().id, c.op))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (rsvp1.id.clone(), ReservationUpdateType::Update as i32),
+                (rsvp2.id, ReservationUpdateType::Create as i32),
+                (rsvp1.id, ReservationUpdateType::Delete as i32),
+            ]
+        );
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn listen_should_replay_more_than_a_page(migrated_pool: Pool<Postgres>) {
+        sqlx::query("INSERT INTO rsvp.reservations (user_id, resource_id, timespan, note) SELECT 'tyrid', 'ocean-view-room-713', tstzrange('2023-01-02'::timestamptz + i * interval '1 hour', '2023-01-02'::timestamptz + (i + 1) * interval '1 hour'), 'hello.' FROM generate_series(0, 1199) AS i")
+            .execute(&migrated_pool)
+            .await
+            .unwrap();
+        let manager = ReservationManager::new(migrated_pool);
+        // unknown change ids replay from the start
+        let mut stream = manager.listen(Some(0)).await.unwrap();
+
+        let mut ids = vec![];
+        for _ in 0..1200 {
+            let change = tokio::time::timeout(Duration::from_secs(5), stream.next())
+                .await
+                .unwrap()
+                .unwrap()
+                .unwrap();
+            ids.push(change.change_id);
+        }
+        assert!(ids.windows(2).all(|w| w[0] < w[1]));
+    }
+}
@@ -1,55 +1,197 @@
-use crate::{ReservationId, ReservationManager, Rsvp};
-use abi::{ReservationStatus, Validator};
+use std::{ops::Range, time::Duration};
+
+use crate::{
+    idempotency, listener, reaper, recurrence, resource, ListenStream, Occurrence, QueryStream,
+    ReservationId, ReservationManager, ResourceId, Rsvp,
+};
+use abi::{CursorDirection, FilterCursor, ReservationStatus, UpdateField, Validator};
+use async_stream::try_stream;
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
-use sqlx::{postgres::types::PgRange, types::Uuid, PgPool, Row};
+use futures::TryStreamExt;
+use prost::Message;
+use sqlx::{
+    postgres::{types::PgRange, PgArguments},
+    query::{QueryAs, QueryScalar},
+    types::Uuid,
+    Connection, FromRow, PgConnection, PgPool, Postgres, Row, Transaction,
+};
+
+// gaps left in the window by the times the resource is full, where at least as many active
+// reservations as its capacity overlap, counted with their buffers like CAPACITY_SQL and
+// the exclusion constraint do. Ends sort before starts at the same time. Full times are
+// extended by the buffers a new reservation would get, so a gap can be reserved as is
+const AVAILABILITY_SQL: &str = "WITH resource AS (SELECT COALESCE((SELECT capacity FROM rsvp.resources WHERE id = $1), 1) AS capacity, COALESCE((SELECT pre_buffer FROM rsvp.resources WHERE id = $1), '0') AS pre, COALESCE((SELECT post_buffer FROM rsvp.resources WHERE id = $1), '0') AS post), spans AS (SELECT effective_timespan AS span FROM rsvp.reservations WHERE resource_id = $1 AND status IN ('pending', 'confirmed')), events AS (SELECT lower(span) AS t, 1 AS d FROM spans UNION ALL SELECT upper(span), -1 FROM spans), counts AS (SELECT t, lead(t) OVER (ORDER BY t, d) AS next, sum(d) OVER (ORDER BY t, d) AS n FROM events), taken AS (SELECT rsvp.pad_timespan(tstzrange(t, next), post, pre) AS span FROM counts, resource WHERE n >= capacity AND next > t) SELECT lower(gap), upper(gap) FROM unnest(tstzmultirange($2::tstzrange) - COALESCE((SELECT range_agg(span) FROM taken WHERE span && $2::tstzrange), '{}'::tstzmultirange)) AS gap ORDER BY lower(gap)";
+
+// active reservations of the resource whose buffered window overlaps the buffered window
+// given, but the one being moved. Buffers count the same as for the exclusion constraint
+const CAPACITY_SQL: &str = "SELECT *, lower(effective_timespan) AS buffered_start, upper(effective_timespan) AS buffered_end FROM rsvp.reservations WHERE resource_id = $1 AND status IN ('pending', 'confirmed') AND effective_timespan && $2 AND id IS DISTINCT FROM $3 ORDER BY lower(timespan), id";
+
+// the booked window of the active reservation holding a buffered window
+const BOOKED_SQL: &str = "SELECT lower(timespan), upper(timespan) FROM rsvp.reservations WHERE resource_id = $1 AND effective_timespan = $2 AND status IN ('pending', 'confirmed') AND exclusive LIMIT 1";
+
+// a window in the time zone of the resource
+const LOCAL_WINDOW_SQL: &str =
+    "SELECT $1::timestamptz AT TIME ZONE $3, $2::timestamptz AT TIME ZONE $3";
+
+const DEFAULT_IDEMPOTENCY_WINDOW: Duration = Duration::from_secs(24 * 60 * 60);
 
 #[async_trait]
 impl Rsvp for ReservationManager {
-    async fn reserve(&self, mut rsvp: abi::Reservation) -> Result<abi::Reservation, abi::Error> {
+    async fn reserve(&self, rsvp: abi::Reservation) -> Result<abi::Reservation, abi::Error> {
+        let mut tx = self.pool.begin().await?;
+        let rsvp = insert(&mut tx, rsvp, None).await?;
+        tx.commit().await?;
+
+        Ok(rsvp)
+    }
+    async fn reserve_idempotent(
+        &self,
+        key: String,
+        rsvp: abi::Reservation,
+    ) -> Result<abi::Reservation, abi::Error> {
+        if key.is_empty() {
+            return self.reserve(rsvp).await;
+        }
+        let mut tx = self.pool.begin().await?;
+        let request = rsvp.encode_to_vec();
+        if let Some(rsvp) =
+            idempotency::claim(&mut tx, "reserve", &key, &request, self.idempotency_window).await?
+        {
+            return Ok(rsvp);
+        }
+        let rsvp = insert(&mut tx, rsvp, None).await?;
+        idempotency::store(&mut tx, "reserve", &key, &rsvp).await?;
+        tx.commit().await?;
+
+        Ok(rsvp)
+    }
+    async fn reserve_batch(
+        &self,
+        rsvps: Vec<abi::Reservation>,
+        mode: abi::BatchMode,
+    ) -> Result<Vec<Result<abi::Reservation, abi::Error>>, abi::Error> {
+        let mut tx = self.pool.begin().await?;
+        let results = insert_each(&mut tx, rsvps, None).await?;
+        commit_batch(tx, results, mode).await
+    }
+    async fn reserve_recurring(
+        &self,
+        rsvp: abi::Reservation,
+        rule: abi::RecurrenceRule,
+        mode: abi::BatchMode,
+    ) -> Result<(String, Vec<Occurrence>), abi::Error> {
         rsvp.validate()?;
+        let mut tx = self.pool.begin().await?;
+        let windows = recurrence::occurrences(&mut tx, &rsvp, &rule).await?;
+        let series_id: Uuid = sqlx::query_scalar("SELECT gen_random_uuid()")
+            .fetch_one(&mut *tx)
+            .await?;
 
-        let timespan: PgRange<DateTime<Utc>> = rsvp.get_timespan();
+        let rsvps = windows
+            .iter()
+            .map(|window| rsvp.with_window(window))
+            .collect();
+        let results = insert_each(&mut tx, rsvps, Some(series_id)).await?;
+        let results = commit_batch(tx, results, mode).await?;
 
-        let status: ReservationStatus = abi::ReservationStatus::try_from(rsvp.status)
-            .unwrap_or(abi::ReservationStatus::Pending);
+        let made = results.iter().any(|result| result.is_ok());
+        let series_id = if made {
+            series_id.to_string()
+        } else {
+            String::new()
+        };
+        Ok((series_id, windows.into_iter().zip(results).collect()))
+    }
+    async fn change_status(
+        &self,
+        id: ReservationId,
+        expected_version: Option<i64>,
+    ) -> Result<abi::Reservation, abi::Error> {
+        self.transition(id, ReservationStatus::Confirmed, expected_version)
+            .await
+    }
+    async fn change_status_idempotent(
+        &self,
+        key: String,
+        id: ReservationId,
+        expected_version: Option<i64>,
+    ) -> Result<abi::Reservation, abi::Error> {
+        if key.is_empty() {
+            return self.change_status(id, expected_version).await;
+        }
+        let id = Uuid::parse_str(&id).map_err(|_| abi::Error::InvalidReservationId(id.clone()))?;
 
-        // generate a insert sql for the reservation
-        let id:Uuid = sqlx::query(
-          "INSERT INTO rsvp.reservations (user_id, resource_id, timespan, note, status) VALUES ($1, $2, $3, $4, $5::rsvp.reservation_status) RETURNING id"
-        ).bind(rsvp.user_id.clone())
-        .bind(rsvp.resource_id.clone())
-        .bind(timespan)
-        .bind(rsvp.note.clone())
-        .bind(status.to_string())
-        .fetch_one(&self.pool).await?.get(0);
+        let mut tx = self.pool.begin().await?;
+        if let Some(rsvp) = idempotency::claim(
+            &mut tx,
+            "confirm",
+            &key,
+            id.as_bytes(),
+            self.idempotency_window,
+        )
+        .await?
+        {
+            return Ok(rsvp);
+        }
+        let rsvp =
+            update_status(&mut tx, id, ReservationStatus::Confirmed, expected_version).await?;
+        idempotency::store(&mut tx, "confirm", &key, &rsvp).await?;
+        tx.commit().await?;
 
-        rsvp.id = id.to_string();
         Ok(rsvp)
     }
-    async fn change_status(&self, id: ReservationId) -> Result<abi::Reservation, abi::Error> {
-        // if reservation.status is pending, change to confirmed, otherwise do nothing.
-
+    async fn transition(
+        &self,
+        id: ReservationId,
+        to: ReservationStatus,
+        expected_version: Option<i64>,
+    ) -> Result<abi::Reservation, abi::Error> {
         let id = Uuid::parse_str(&id).map_err(|_| abi::Error::InvalidReservationId(id.clone()))?;
 
-        let sql = "UPDATE rsvp.reservations SET status = 'confirmed' WHERE id = $1 AND status = 'pending' RETURNING *";
-        let reservation: abi::Reservation =
-            sqlx::query_as(sql).bind(id).fetch_one(&self.pool).await?;
+        let mut tx = self.pool.begin().await?;
+        let reservation = update_status(&mut tx, id, to, expected_version).await?;
+        tx.commit().await?;
+
         Ok(reservation)
     }
     async fn update_note(
         &self,
         id: ReservationId,
         note: String,
+        expected_version: Option<i64>,
     ) -> Result<abi::Reservation, abi::Error> {
         let id = Uuid::parse_str(&id).map_err(|_| abi::Error::InvalidReservationId(id.clone()))?;
-        let sql = "UPDATE rsvp.reservations SET note = $1 WHERE id = $2 RETURNING *";
-        let reservation: abi::Reservation = sqlx::query_as(sql)
+        let sql = "UPDATE rsvp.reservations SET note = $1 WHERE id = $2 AND ($3::int8 IS NULL OR version = $3) RETURNING *";
+        let result = sqlx::query_as(sql)
             .bind(note)
             .bind(id)
+            .bind(expected_version)
             .fetch_one(&self.pool)
-            .await?;
-        Ok(reservation)
+            .await;
+        self.check_version(id, expected_version, result).await
+    }
+
+    async fn update(&self, request: abi::UpdateRequest) -> Result<abi::Reservation, abi::Error> {
+        request.validate()?;
+        let fields = request.get_fields()?;
+        let id = Uuid::parse_str(&request.id)
+            .map_err(|_| abi::Error::InvalidReservationId(request.id.clone()))?;
+        let rsvp = request.reservation.unwrap_or_default();
+
+        // every updatable field is in the statement, those not in the mask keep their value
+        let sql = "UPDATE rsvp.reservations SET note = CASE WHEN $2 THEN $3 ELSE note END, user_id = CASE WHEN $4 THEN $5 ELSE user_id END WHERE id = $1 AND ($6::int8 IS NULL OR version = $6) RETURNING *";
+        let result = sqlx::query_as(sql)
+            .bind(id)
+            .bind(fields.contains(&UpdateField::Note))
+            .bind(rsvp.note)
+            .bind(fields.contains(&UpdateField::UserId))
+            .bind(rsvp.user_id)
+            .bind(request.expected_version)
+            .fetch_one(&self.pool)
+            .await;
+        self.check_version(id, request.expected_version, result)
+            .await
     }
 
     async fn get(&self, id: ReservationId) -> Result<abi::Reservation, abi::Error> {
@@ -60,44 +202,570 @@ impl Rsvp for ReservationManager {
         Ok(rsvp)
     }
 
-    async fn delete(&self, id: ReservationId) -> Result<(), abi::Error> {
+    async fn reschedule(
+        &self,
+        id: ReservationId,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        resource_id: Option<ResourceId>,
+        expected_version: Option<i64>,
+    ) -> Result<abi::Reservation, abi::Error> {
+        let id = Uuid::parse_str(&id).map_err(|_| abi::Error::InvalidReservationId(id.clone()))?;
+        if start >= end {
+            return Err(abi::Error::InvalidTime);
+        }
+        if matches!(resource_id.as_deref(), Some("")) {
+            return Err(abi::Error::InvalidResourceId("".to_string()));
+        }
+        let timespan = PgRange::from(start..end);
+
+        let mut tx = self.pool.begin().await?;
+        // a reservation can only move to a resource that can be reserved
+        let resource = match resource_id.as_deref() {
+            Some(resource_id) => resource::lock_bookable(&mut tx, resource_id).await?,
+            None => resource::lock_reserved(&mut tx, id).await?,
+        };
+        // like when reserving, blocked reservations are exempt from the booking rules
+        let blocked: Option<bool> =
+            sqlx::query_scalar("SELECT status = 'blocked' FROM rsvp.reservations WHERE id = $1")
+                .bind(id)
+                .fetch_optional(&mut *tx)
+                .await?;
+        if blocked == Some(false) {
+            check_rules(&mut tx, &resource, &(start..end)).await?;
+        }
+        let exclusive = resource.get_capacity() == 1;
+        if !exclusive {
+            check_capacity(&mut tx, &resource, start..end, Some(id)).await?;
+        }
+        // a single statement, so the reservation either moves or stays where it was if the
+        // exclusion constraint rejects the new window
+        // the reservation takes the current buffers of the resource it ends up on
+        let rules = resource.get_rules();
+        let sql = "UPDATE rsvp.reservations SET timespan = $2, resource_id = $3, exclusive = $5, pre_buffer = $6, post_buffer = $7 WHERE id = $1 AND ($4::int8 IS NULL OR version = $4) RETURNING *";
+        // in a savepoint, so the reservation in the way can still be looked up on conflict
+        let mut savepoint = tx.begin().await?;
+        let result = sqlx::query_as(sql)
+            .bind(id)
+            .bind(timespan)
+            .bind(&resource.id)
+            .bind(expected_version)
+            .bind(exclusive)
+            .bind(
+                rules
+                    .get_pre_buffer()
+                    .unwrap_or_else(chrono::Duration::zero),
+            )
+            .bind(
+                rules
+                    .get_post_buffer()
+                    .unwrap_or_else(chrono::Duration::zero),
+            )
+            .fetch_one(&mut *savepoint)
+            .await;
+        if result.is_ok() {
+            savepoint.commit().await?;
+        } else {
+            savepoint.rollback().await?;
+        }
+        let rsvp = match self.check_version(id, expected_version, result).await {
+            Ok(rsvp) => rsvp,
+            Err(e) => return Err(booked_conflict(&mut tx, e, start..end).await),
+        };
+        tx.commit().await?;
+
+        Ok(rsvp)
+    }
+
+    async fn cancel(
+        &self,
+        id: ReservationId,
+        expected_version: Option<i64>,
+    ) -> Result<abi::Reservation, abi::Error> {
+        self.transition(id, ReservationStatus::Cancelled, expected_version)
+            .await
+    }
+
+    async fn delete(
+        &self,
+        id: ReservationId,
+        expected_version: Option<i64>,
+    ) -> Result<(), abi::Error> {
         let id = Uuid::parse_str(&id).map_err(|_| abi::Error::InvalidReservationId(id.clone()))?;
-        let sql = "DELETE FROM rsvp.reservations WHERE id = $1";
-        sqlx::query(sql).bind(id).execute(&self.pool).await?;
+        let sql =
+            "DELETE FROM rsvp.reservations WHERE id = $1 AND ($2::int8 IS NULL OR version = $2)";
+        let result = sqlx::query(sql)
+            .bind(id)
+            .bind(expected_version)
+            .execute(&self.pool)
+            .await?;
+        // deleting a missing reservation is fine unless a version was expected
+        if result.rows_affected() == 0 && expected_version.is_some() {
+            self.check_version(id, expected_version, Err(sqlx::Error::RowNotFound))
+                .await?;
+        }
         Ok(())
     }
     async fn query(
         &self,
         query: abi::ReservationQuery,
     ) -> Result<Vec<abi::Reservation>, abi::Error> {
-        let user_id = str_to_option(&query.user_id);
-        let resource_id = str_to_option(&query.resource_id);
-        let range: PgRange<DateTime<Utc>> = query.get_timespan();
-        let status =
-            ReservationStatus::try_from(query.status).unwrap_or(ReservationStatus::Pending);
-        let rsvps = sqlx::query_as(
-            "SELECT * FROM rsvp.query($1,$2,$3,$4::rsvp.reservation_status,$5,$6,$7)",
-        )
-        .bind(user_id)
-        .bind(resource_id)
-        .bind(range)
-        .bind(status.to_string())
-        .bind(query.page)
-        .bind(query.desc)
-        .bind(query.page_size)
-        .fetch_all(&self.pool)
+        self.query_stream(query).await?.try_collect().await
+    }
+
+    async fn query_stream(&self, query: abi::ReservationQuery) -> Result<QueryStream, abi::Error> {
+        query.validate()?;
+        let pool = self.pool.clone();
+
+        let stream = try_stream! {
+            let mut rsvps = query_rows(&query).fetch(&pool);
+            while let Some(rsvp) = rsvps.try_next().await? {
+                yield rsvp;
+            }
+        };
+
+        Ok(Box::pin(stream))
+    }
+
+    async fn query_page(
+        &self,
+        query: abi::ReservationQuery,
+    ) -> Result<(abi::QueryPager, QueryStream), abi::Error> {
+        query.validate()?;
+        // the count and the rows are read from the same snapshot, so they agree
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ, READ ONLY")
+            .execute(&mut *tx)
+            .await?;
+        let total = query_count(&query).fetch_one(&mut *tx).await?;
+        let pager = abi::QueryPager::new(&query, total);
+
+        let stream = try_stream! {
+            let mut rsvps = query_rows(&query).fetch(&mut *tx);
+            while let Some(rsvp) = rsvps.try_next().await? {
+                yield rsvp;
+            }
+            drop(rsvps);
+            tx.commit().await?;
+        };
+
+        Ok((pager, Box::pin(stream)))
+    }
+
+    async fn availability(
+        &self,
+        resource_id: ResourceId,
+        window: Range<DateTime<Utc>>,
+        min_duration: chrono::Duration,
+        granularity: chrono::Duration,
+    ) -> Result<Vec<abi::TimeSlot>, abi::Error> {
+        if resource_id.is_empty() {
+            return Err(abi::Error::InvalidResourceId(resource_id));
+        }
+        if window.start >= window.end {
+            return Err(abi::Error::InvalidTime);
+        }
+
+        let gaps: Vec<(DateTime<Utc>, DateTime<Utc>)> = sqlx::query_as(AVAILABILITY_SQL)
+            .bind(resource_id)
+            .bind(PgRange::from(window))
+            .fetch_all(&self.pool)
+            .await?;
+        let slots = gaps
+            .into_iter()
+            .filter_map(|(start, end)| fit_slot(start..end, min_duration, granularity))
+            .map(|slot| abi::TimeSlot::new(slot.start, slot.end))
+            .collect();
+
+        Ok(slots)
+    }
+
+    async fn filter(
+        &self,
+        filter: abi::ReservationFilter,
+    ) -> Result<(abi::FilterPager, Vec<abi::Reservation>), abi::Error> {
+        filter.validate()?;
+        let cursor = filter.get_cursor()?;
+        let user_id = str_to_option(&filter.user_id);
+        let resource_id = str_to_option(&filter.resource_id);
+        let statuses = filter.get_statuses();
+
+        // scan backwards from a `Before` cursor, FilterPager puts the page back in order
+        let backward = matches!(
+            cursor,
+            Some(FilterCursor {
+                direction: CursorDirection::Before,
+                ..
+            })
+        );
+        let (op, order) = if filter.desc != backward {
+            ("<", "DESC")
+        } else {
+            (">", "ASC")
+        };
+        let sql = format!(
+            "SELECT * FROM rsvp.reservations WHERE ($1::text IS NULL OR user_id = $1) AND ($2::text IS NULL OR resource_id = $2) AND (cardinality($3::text[]) = 0 OR status = ANY($3::text[]::rsvp.reservation_status[])) AND $7::tstzrange @> timespan AND ($4::timestamptz IS NULL OR (lower(timespan), id) {op} ($4, $5)) ORDER BY lower(timespan) {order}, id {order} LIMIT $6"
+        );
+        let rsvps = sqlx::query_as(&sql)
+            .bind(user_id)
+            .bind(resource_id)
+            .bind(statuses)
+            .bind(cursor.as_ref().map(|c| c.start))
+            .bind(cursor.as_ref().map(|c| c.id))
+            .bind(filter.get_page_size() + 1)
+            .bind(filter.get_timespan())
+            .fetch_all(&self.pool)
+            .await?;
+
+        abi::FilterPager::paginate(&filter, rsvps)
+    }
+
+    async fn expire_holds(&self) -> Result<Vec<abi::Reservation>, abi::Error> {
+        reaper::expire_holds(&self.pool).await
+    }
+
+    async fn listen(&self, since: Option<i64>) -> Result<ListenStream, abi::Error> {
+        listener::listen(self.pool.clone(), since).await
+    }
+}
+
+// insert each reservation within the transaction of `conn` in a savepoint of its own, so a
+// failed one doesn't undo the others
+async fn insert_each(
+    conn: &mut PgConnection,
+    rsvps: Vec<abi::Reservation>,
+    series_id: Option<Uuid>,
+) -> Result<Vec<Result<abi::Reservation, abi::Error>>, abi::Error> {
+    let mut results = Vec::with_capacity(rsvps.len());
+    for rsvp in rsvps {
+        let mut savepoint = conn.begin().await?;
+        let result = insert(&mut savepoint, rsvp, series_id).await;
+        if result.is_ok() {
+            savepoint.commit().await?;
+        } else {
+            savepoint.rollback().await?;
+        }
+        results.push(result);
+    }
+
+    Ok(results)
+}
+
+// commit the reservations inserted by insert_each, unless one failed in atomic mode, which
+// undoes them all
+async fn commit_batch(
+    tx: Transaction<'_, Postgres>,
+    results: Vec<Result<abi::Reservation, abi::Error>>,
+    mode: abi::BatchMode,
+) -> Result<Vec<Result<abi::Reservation, abi::Error>>, abi::Error> {
+    let failed = results.iter().any(|result| result.is_err());
+    if failed && mode == abi::BatchMode::Atomic {
+        tx.rollback().await?;
+        let results = results
+            .into_iter()
+            .map(|result| result.and(Err(abi::Error::BatchAborted)))
+            .collect();
+        return Ok(results);
+    }
+    tx.commit().await?;
+
+    Ok(results)
+}
+
+// insert a reservation within the transaction of `conn`, only pending reservations are
+// held until they expire. Occurrences of a recurring reservation carry its series id
+async fn insert(
+    conn: &mut PgConnection,
+    rsvp: abi::Reservation,
+    series_id: Option<Uuid>,
+) -> Result<abi::Reservation, abi::Error> {
+    rsvp.validate()?;
+    let resource = resource::lock_bookable(&mut *conn, &rsvp.resource_id).await?;
+
+    let timespan: PgRange<DateTime<Utc>> = rsvp.get_timespan();
+
+    let status: ReservationStatus =
+        abi::ReservationStatus::try_from(rsvp.status).unwrap_or(abi::ReservationStatus::Pending);
+
+    // admins block resources regardless of the booking rules
+    let rules = resource.get_rules();
+    if status != ReservationStatus::Blocked {
+        check_rules(&mut *conn, &resource, &rsvp.get_window()?).await?;
+    }
+    let exclusive = resource.get_capacity() == 1;
+    let active = matches!(
+        status,
+        ReservationStatus::Pending | ReservationStatus::Confirmed
+    );
+    if !exclusive && active {
+        check_capacity(&mut *conn, &resource, rsvp.get_window()?, None).await?;
+    }
+
+    // generate a insert sql for the reservation, in a savepoint so the reservation in the
+    // way can still be looked up on conflict
+    let window = rsvp.get_window()?;
+    let mut savepoint = conn.begin().await?;
+    let result: Result<abi::Reservation, _> = sqlx::query_as(
+        "INSERT INTO rsvp.reservations (user_id, resource_id, timespan, note, status, expires_at, exclusive, pre_buffer, post_buffer, series_id) VALUES ($1, $2, $3, $4, $5::rsvp.reservation_status, CASE WHEN $5 = 'pending' THEN now() + make_interval(secs => $6::float8) END, $7, $8, $9, $10) RETURNING *"
+    )
+    .bind(rsvp.user_id.clone())
+    .bind(rsvp.resource_id.clone())
+    .bind(timespan)
+    .bind(rsvp.note.clone())
+    .bind(status.to_string())
+    .bind(rsvp.get_hold_ttl())
+    .bind(exclusive)
+    .bind(rules.get_pre_buffer().unwrap_or_else(chrono::Duration::zero))
+    .bind(rules.get_post_buffer().unwrap_or_else(chrono::Duration::zero))
+    .bind(series_id)
+    .fetch_one(&mut *savepoint)
+    .await;
+    let mut reservation = match result {
+        Ok(reservation) => {
+            savepoint.commit().await?;
+            reservation
+        }
+        Err(e) => {
+            savepoint.rollback().await?;
+            return Err(booked_conflict(conn, e.into(), window).await);
+        }
+    };
+
+    // the ttl is not stored, keep the one requested
+    reservation.hold_ttl = rsvp.hold_ttl;
+    Ok(reservation)
+}
+
+// the exclusion constraint reports the buffered windows it compares. Report the booked
+// ones instead: the new window is `booked`, the one in the way is looked up within the
+// transaction of `conn`
+async fn booked_conflict(
+    conn: &mut PgConnection,
+    err: abi::Error,
+    booked: Range<DateTime<Utc>>,
+) -> abi::Error {
+    let abi::Error::ConflictReservation(abi::ReservationConflictInfo::Parsed(conflict)) = err
+    else {
+        return err;
+    };
+    let old = match conflict.old.buffered.clone() {
+        Some(buffered) => sqlx::query_as(BOOKED_SQL)
+            .bind(&conflict.old.rid)
+            .bind(PgRange::from(buffered))
+            .fetch_optional(conn)
+            .await
+            .ok()
+            .flatten()
+            .map(|(start, end)| start..end),
+        None => None,
+    };
+
+    abi::Error::ConflictReservation(abi::ReservationConflictInfo::Parsed(Box::new(
+        conflict.booked(booked, old),
+    )))
+}
+
+// reservations of a resource with a capacity above 1 may overlap, as long as no more than
+// the capacity do at any time. Checked within the transaction of `conn`, leaving out
+// the reservation being moved if any
+// check a window against the booking rules of the resource, slots being aligned in its
+// time zone
+async fn check_rules(
+    conn: &mut PgConnection,
+    resource: &abi::Resource,
+    window: &Range<DateTime<Utc>>,
+) -> Result<(), abi::Error> {
+    let (start, end) = sqlx::query_as(LOCAL_WINDOW_SQL)
+        .bind(window.start)
+        .bind(window.end)
+        .bind(resource.get_timezone())
+        .fetch_one(conn)
+        .await?;
+    resource
+        .get_rules()
+        .check(window, &(start..end), Utc::now())
+}
+
+async fn check_capacity(
+    conn: &mut PgConnection,
+    resource: &abi::Resource,
+    window: Range<DateTime<Utc>>,
+    moving: Option<Uuid>,
+) -> Result<(), abi::Error> {
+    // the exclusion constraint doesn't cover these reservations, concurrent ones would
+    // miss each other if they didn't take turns
+    resource::lock_reservations(&mut *conn, &resource.id).await?;
+    let rules = resource.get_rules();
+    let buffered = window.start
+        - rules
+            .get_pre_buffer()
+            .unwrap_or_else(chrono::Duration::zero)
+        ..window.end
+            + rules
+                .get_post_buffer()
+                .unwrap_or_else(chrono::Duration::zero);
+    let rows = sqlx::query(CAPACITY_SQL)
+        .bind(&resource.id)
+        .bind(PgRange::from(buffered.clone()))
+        .bind(moving)
+        .fetch_all(conn)
         .await?;
+    let mut overlapping = Vec::with_capacity(rows.len());
+    let mut windows = Vec::with_capacity(rows.len());
+    for row in rows {
+        overlapping.push(abi::Reservation::from_row(&row)?);
+        windows.push(row.get("buffered_start")..row.get("buffered_end"));
+    }
+
+    let capacity = resource.get_capacity();
+    if max_overlap(&buffered, &windows) < capacity as usize {
+        return Ok(());
+    }
+    let conflict = abi::CapacityConflict::new(&resource.id, window, capacity, overlapping);
+    Err(abi::Error::ConflictReservation(
+        abi::ReservationConflictInfo::Capacity(conflict),
+    ))
+}
+
+// the most windows overlapping at the same time within the window, which is reached
+// where the window or one of the others starts
+fn max_overlap(window: &Range<DateTime<Utc>>, windows: &[Range<DateTime<Utc>>]) -> usize {
+    std::iter::once(window.start)
+        .chain(
+            windows
+                .iter()
+                .map(|w| w.start)
+                .filter(|t| window.contains(t)),
+        )
+        .map(|t| windows.iter().filter(|w| w.contains(&t)).count())
+        .max()
+        .unwrap_or(0)
+}
+
+// round a free gap inwards to multiples of the granularity, dropping it if it becomes
+// shorter than min_duration
+fn fit_slot(
+    gap: Range<DateTime<Utc>>,
+    min_duration: chrono::Duration,
+    granularity: chrono::Duration,
+) -> Option<Range<DateTime<Utc>>> {
+    let step = granularity.num_microseconds().unwrap_or(0);
+    let slot = if step > 0 {
+        let start = gap.start.timestamp_micros();
+        let end = gap.end.timestamp_micros();
+        let start = start.div_euclid(step) * step + if start % step == 0 { 0 } else { step };
+        let end = end.div_euclid(step) * step;
+        DateTime::from_timestamp_micros(start)?..DateTime::from_timestamp_micros(end)?
+    } else {
+        gap
+    };
+
+    let duration = slot.end - slot.start;
+    (duration > chrono::Duration::zero() && duration >= min_duration).then_some(slot)
+}
+
+// move a reservation to another status within the transaction of `conn`
+async fn update_status(
+    conn: &mut PgConnection,
+    id: Uuid,
+    to: ReservationStatus,
+    expected_version: Option<i64>,
+) -> Result<abi::Reservation, abi::Error> {
+    // lock the row so the status checked is the one being changed
+    let sql = "SELECT *, COALESCE(expires_at <= now(), FALSE) AS expired FROM rsvp.reservations WHERE id = $1 FOR UPDATE";
+    let row = sqlx::query(sql).bind(id).fetch_one(&mut *conn).await?;
+    let current = abi::Reservation::from_row(&row)?;
+    if matches!(expected_version, Some(version) if version != current.version) {
+        return Err(abi::Error::VersionMismatch {
+            current: current.version,
+        });
+    }
 
-        Ok(rsvps)
+    // only pending holds and the holds cancelled by the reaper have expires_at set
+    let expired: bool = row.get("expired");
+    if expired && to == ReservationStatus::Confirmed {
+        return Err(abi::Error::HoldExpired);
     }
+    let from = ReservationStatus::try_from(current.status).unwrap_or(ReservationStatus::Unknown);
+    from.transition(to)?;
+
+    // the reservation is no longer a hold once it leaves pending
+    let sql = "UPDATE rsvp.reservations SET status = $2::rsvp.reservation_status, expires_at = NULL WHERE id = $1 RETURNING *";
+    let reservation = sqlx::query_as(sql)
+        .bind(id)
+        .bind(to.to_string())
+        .fetch_one(&mut *conn)
+        .await?;
+
+    Ok(reservation)
 }
 
 impl ReservationManager {
     pub fn new(pool: PgPool) -> Self {
-        Self { pool }
+        Self {
+            pool,
+            idempotency_window: DEFAULT_IDEMPOTENCY_WINDOW,
+        }
+    }
+
+    /// Set how long retried calls with the same idempotency key get the original response
+    pub fn with_idempotency_window(mut self, window: Duration) -> Self {
+        self.idempotency_window = window;
+        self
+    }
+
+    // a write guarded by an expected version matched no row, either the reservation
+    // is gone or it has moved on to another version
+    async fn check_version<T: Send>(
+        &self,
+        id: Uuid,
+        expected_version: Option<i64>,
+        result: Result<T, sqlx::Error>,
+    ) -> Result<T, abi::Error> {
+        match result {
+            Err(sqlx::Error::RowNotFound) if expected_version.is_some() => {
+                let sql = "SELECT version FROM rsvp.reservations WHERE id = $1";
+                let current: Option<i64> = sqlx::query_scalar(sql)
+                    .bind(id)
+                    .fetch_optional(&self.pool)
+                    .await?;
+                match current {
+                    Some(current) => Err(abi::Error::VersionMismatch { current }),
+                    None => Err(abi::Error::NotFound),
+                }
+            }
+            result => Ok(result?),
+        }
     }
 }
 
+// the reservations of the requested page
+fn query_rows(
+    query: &abi::ReservationQuery,
+) -> QueryAs<'_, Postgres, abi::Reservation, PgArguments> {
+    sqlx::query_as(
+        "SELECT * FROM rsvp.query($1,$2,$3,$4::text[]::rsvp.reservation_status[],$5,$6,$7,$8)",
+    )
+    .bind(str_to_option(&query.user_id))
+    .bind(str_to_option(&query.resource_id))
+    .bind(query.get_timespan())
+    .bind(query.get_statuses())
+    .bind(query.page)
+    .bind(query.desc)
+    .bind(query.get_page_size() as i32)
+    .bind(query.get_mode().to_string())
+}
+
+// the number of reservations matching the query, on every page
+fn query_count(query: &abi::ReservationQuery) -> QueryScalar<'_, Postgres, i64, PgArguments> {
+    sqlx::query_scalar("SELECT rsvp.query_count($1,$2,$3,$4::text[]::rsvp.reservation_status[],$5)")
+        .bind(str_to_option(&query.user_id))
+        .bind(str_to_option(&query.resource_id))
+        .bind(query.get_timespan())
+        .bind(query.get_statuses())
+        .bind(query.get_mode().to_string())
+}
+
 fn str_to_option(s: &str) -> Option<&str> {
     if s.is_empty() {
         None
@@ -109,12 +777,13 @@ fn str_to_option(s: &str) -> Option<&str> {
 #[cfg(test)]
 mod tests {
     use abi::ReservationConflictInfo;
+    use chrono::{Duration, FixedOffset};
     use prost_types::Timestamp;
     use sqlx::{Pool, Postgres};
 
     use super::*;
 
-    #[sqlx::test(migrations = "../migrations")]
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
     async fn reserve_should_work_for_valid_window(migrated_pool: Pool<Postgres>) {
         let manager = ReservationManager::new(migrated_pool.clone());
         let rsvp = abi::Reservation::new_pending(
@@ -129,7 +798,7 @@ mod tests {
         assert!(!rsvp.id.is_empty());
     }
 
-    #[sqlx::test(migrations = "../migrations")]
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
     async fn reserve_conflict_reservation_should_reject(pool: Pool<Postgres>) {
         let manager = ReservationManager::new(pool.clone());
         let rsvp1 = abi::Reservation::new_pending(
@@ -161,104 +830,1147 @@ mod tests {
         }
     }
 
-    #[sqlx::test(migrations = "../migrations")]
-    async fn reservation_change_status_should_work(migrated_pool: Pool<Postgres>) {
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn reserve_over_inactive_reservation_should_work(migrated_pool: Pool<Postgres>) {
         let manager = ReservationManager::new(migrated_pool.clone());
-        let rsvp = abi::Reservation::new_pending(
-            "tyrid",
-            "ocean-view-room-714",
+        let mut blocked = abi::Reservation::new_pending(
+            "adminid",
+            "ocean-view-room-713",
             "2022-12-25T15:00:00-0700".parse().unwrap(),
             "2022-12-28T12:00:00-0700".parse().unwrap(),
-            "I'll arrive at 3pm. Please help to upgrade to execuitive room if possible.",
+            "maintenance.",
         );
+        blocked.status = ReservationStatus::Blocked as i32;
+        manager.reserve(blocked).await.unwrap();
+        let cancelled = abi::Reservation::new_pending(
+            "aliceid",
+            "ocean-view-room-713",
+            "2022-12-26T15:00:00-0700".parse().unwrap(),
+            "2022-12-27T12:00:00-0700".parse().unwrap(),
+            "hello.",
+        );
+        let cancelled = manager.reserve(cancelled).await.unwrap();
+        manager.cancel(cancelled.id, None).await.unwrap();
 
-        let rsvp = manager.reserve(rsvp).await.unwrap();
-        let rsvp = manager.change_status(rsvp.id).await.unwrap();
-        assert_eq!(rsvp.status, ReservationStatus::Confirmed as i32);
-    }
-
-    #[sqlx::test(migrations = "../migrations")]
-    async fn reservation_change_status_not_pending_should_do_nothing(
-        migrated_pool: Pool<Postgres>,
-    ) {
-        let manager = ReservationManager::new(migrated_pool.clone());
+        // overlaps both the blocked and the cancelled reservation
         let rsvp = abi::Reservation::new_pending(
             "tyrid",
-            "ocean-view-room-714",
-            "2022-12-25T15:00:00-0700".parse().unwrap(),
-            "2022-12-28T12:00:00-0700".parse().unwrap(),
-            "I'll arrive at 3pm. Please help to upgrade to execuitive room if possible.",
+            "ocean-view-room-713",
+            "2022-12-26T10:00:00-0700".parse().unwrap(),
+            "2022-12-29T12:00:00-0700".parse().unwrap(),
+            "hello.",
         );
-
         let rsvp = manager.reserve(rsvp).await.unwrap();
-        let rsvp = manager.change_status(rsvp.id).await.unwrap();
+        manager.change_status(rsvp.id, None).await.unwrap();
 
-        // change status agins should do nothing
-        let err = manager.change_status(rsvp.id).await.unwrap_err();
-        assert_eq!(err, abi::Error::NotFound);
+        // the active reservation still holds its slot
+        let rsvp = abi::Reservation::new_pending(
+            "bobid",
+            "ocean-view-room-713",
+            "2022-12-28T15:00:00-0700".parse().unwrap(),
+            "2022-12-30T12:00:00-0700".parse().unwrap(),
+            "hello.",
+        );
+        let err = manager.reserve(rsvp).await.unwrap_err();
+        if let abi::Error::ConflictReservation(ReservationConflictInfo::Parsed(info)) = err {
+            assert_eq!(info.old.start.to_rfc3339(), "2022-12-26T17:00:00+00:00");
+        } else {
+            panic!("expect conflict reservation error, got {:?}", err);
+        }
     }
 
-    #[sqlx::test(migrations = "../migrations")]
-    async fn update_note_should_work(migrated_pool: Pool<Postgres>) {
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn reserve_unknown_or_inactive_resource_should_reject(migrated_pool: Pool<Postgres>) {
         let manager = ReservationManager::new(migrated_pool.clone());
-        let rsvp = abi::Reservation::new_pending(
-            "tyrid",
-            "ocean-view-room-714",
-            "2022-12-25T15:00:00-0700".parse().unwrap(),
-            "2022-12-28T12:00:00-0700".parse().unwrap(),
-            "I'll arrive at 3pm. Please help to upgrade to execuitive room if possible.",
+        let rsvp = |rid: &str| {
+            abi::Reservation::new_pending(
+                "tyrid",
+                rid,
+                "2022-12-25T15:00:00-0700".parse().unwrap(),
+                "2022-12-28T12:00:00-0700".parse().unwrap(),
+                "hello.",
+            )
+        };
+
+        let err = manager
+            .reserve(rsvp("ocean-view-rom-713"))
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err,
+            abi::Error::ResourceNotFound("ocean-view-rom-713".to_string())
         );
 
-        let rsvp = manager.reserve(rsvp).await.unwrap();
-        let rsvp = manager
-            .update_note(rsvp.id, "I'll arrive at 4pm.".to_string())
+        sqlx::query("UPDATE rsvp.resources SET active = FALSE WHERE id = 'ocean-view-room-714'")
+            .execute(&migrated_pool)
             .await
             .unwrap();
+        let err = manager
+            .reserve(rsvp("ocean-view-room-714"))
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err,
+            abi::Error::ResourceInactive("ocean-view-room-714".to_string())
+        );
 
-        assert_eq!(rsvp.note, "I'll arrive at 4pm.");
+        // existing reservations can't be moved onto it either
+        let rsvp = manager.reserve(rsvp("ocean-view-room-713")).await.unwrap();
+        let err = manager
+            .reschedule(
+                rsvp.id,
+                "2022-12-25T22:00:00Z".parse().unwrap(),
+                "2022-12-28T19:00:00Z".parse().unwrap(),
+                Some("ocean-view-room-714".to_string()),
+                None,
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err,
+            abi::Error::ResourceInactive("ocean-view-room-714".to_string())
+        );
     }
 
-    #[sqlx::test(migrations = "../migrations")]
-    async fn get_reservation_should_work(migrated_pool: Pool<Postgres>) {
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn reserve_should_align_slots_in_resource_time_zone(migrated_pool: Pool<Postgres>) {
         let manager = ReservationManager::new(migrated_pool.clone());
-        let rsvp = abi::Reservation::new_pending(
-            "tyrid",
-            "ocean-view-room-714",
-            "2022-12-25T15:00:00-0700".parse().unwrap(),
-            "2022-12-28T12:00:00-0700".parse().unwrap(),
-            "I'll arrive at 3pm. Please help to upgrade to execuitive room if possible.",
-        );
-
-        let rsvp = manager.reserve(rsvp).await.unwrap();
-        let rsvp = manager.get(rsvp.id).await.unwrap();
+        sqlx::query("UPDATE rsvp.resources SET timezone = 'Asia/Kolkata', slot_alignment = '1 hour' WHERE id = 'ocean-view-room-715'")
+            .execute(&migrated_pool)
+            .await
+            .unwrap();
+        let day = (Utc::now() + Duration::days(2))
+            .date_naive()
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+            .and_utc();
+        let rsvp = |minutes: i64| {
+            let start = day + Duration::minutes(minutes);
+            abi::Reservation::new_pending(
+                "tyrid",
+                "ocean-view-room-715",
+                start.fixed_offset(),
+                (start + Duration::hours(1)).fixed_offset(),
+                "hello.",
+            )
+        };
 
-        assert_eq!(rsvp.user_id, "tyrid");
+        // 10:00 in Kolkata, UTC+05:30
+        manager.reserve(rsvp(270)).await.unwrap();
+        // 12:30 there, although on the hour in UTC
+        let err = manager.reserve(rsvp(420)).await.unwrap_err();
+        assert_eq!(err, abi::Error::MisalignedReservation { alignment: 3600 });
     }
 
-    #[sqlx::test(migrations = "../migrations")]
-    async fn delete_reservation_should_work(migrated_pool: Pool<Postgres>) {
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn reserve_should_enforce_booking_rules(migrated_pool: Pool<Postgres>) {
         let manager = ReservationManager::new(migrated_pool.clone());
-        let rsvp = abi::Reservation::new_pending(
-            "tyrid",
-            "ocean-view-room-714",
-            "2022-12-25T15:00:00-0700".parse().unwrap(),
-            "2022-12-28T12:00:00-0700".parse().unwrap(),
-            "I'll arrive at 3pm. Please help to upgrade to execuitive room if possible.",
-        );
+        sqlx::query("UPDATE rsvp.resources SET min_duration = '30 minutes', max_duration = '4 hours', min_lead_time = '1 hour', max_horizon = '30 days', slot_alignment = '15 minutes' WHERE id = 'ocean-view-room-715'")
+            .execute(&migrated_pool)
+            .await
+            .unwrap();
+        // midnight two days ahead, well within the lead time and horizon
+        let day = (Utc::now() + Duration::days(2))
+            .date_naive()
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+            .and_utc();
+        let rsvp = |start: DateTime<Utc>, end: DateTime<Utc>| {
+            abi::Reservation::new_pending(
+                "tyrid",
+                "ocean-view-room-715",
+                start.fixed_offset(),
+                end.fixed_offset(),
+                "hello.",
+            )
+        };
+        let at = |minutes: i64| day + Duration::minutes(minutes);
 
-        let rsvp = manager.reserve(rsvp).await.unwrap();
-        manager.delete(rsvp.id).await.unwrap();
+        let booked = manager.reserve(rsvp(at(600), at(660))).await.unwrap();
+
+        let err = manager.reserve(rsvp(at(720), at(735))).await.unwrap_err();
+        assert_eq!(err, abi::Error::ReservationTooShort { min: 1800 });
+        let err = manager.reserve(rsvp(at(720), at(1020))).await.unwrap_err();
+        assert_eq!(err, abi::Error::ReservationTooLong { max: 14400 });
+        let err = manager.reserve(rsvp(at(720), at(785))).await.unwrap_err();
+        assert_eq!(err, abi::Error::MisalignedReservation { alignment: 900 });
+
+        let soon = Utc::now() + Duration::minutes(30);
+        let err = manager
+            .reserve(rsvp(soon, soon + Duration::hours(1)))
+            .await
+            .unwrap_err();
+        assert_eq!(err, abi::Error::LeadTimeTooShort { min: 3600 });
+        let later = Utc::now() + Duration::days(40);
+        let err = manager
+            .reserve(rsvp(later, later + Duration::hours(1)))
+            .await
+            .unwrap_err();
+        assert_eq!(err, abi::Error::BeyondBookingHorizon { max: 2592000 });
+
+        // moving a reservation is held to the same rules
+        let reschedule = |start: DateTime<Utc>, end: DateTime<Utc>| {
+            manager.reschedule(booked.id.clone(), start, end, None, None)
+        };
+        let err = reschedule(at(720), at(735)).await.unwrap_err();
+        assert_eq!(err, abi::Error::ReservationTooShort { min: 1800 });
+        let err = reschedule(at(720), at(785)).await.unwrap_err();
+        assert_eq!(err, abi::Error::MisalignedReservation { alignment: 900 });
+        let err = reschedule(soon, soon + Duration::hours(1))
+            .await
+            .unwrap_err();
+        assert_eq!(err, abi::Error::LeadTimeTooShort { min: 3600 });
+        reschedule(at(720), at(780)).await.unwrap();
+
+        // blocking the resource ignores the rules
+        let mut blocked = rsvp(at(900), at(905));
+        blocked.status = ReservationStatus::Blocked as i32;
+        let blocked = manager.reserve(blocked).await.unwrap();
+        manager
+            .reschedule(blocked.id, at(910), at(915), None, None)
+            .await
+            .unwrap();
     }
 
-    #[sqlx::test(migrations = "../migrations")]
-    async fn query_reservation_should_work(migrated_pool: Pool<Postgres>) {
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn reserve_should_keep_buffers_free(migrated_pool: Pool<Postgres>) {
         let manager = ReservationManager::new(migrated_pool.clone());
-        let query = abi::ReservationQueryBuilder::default()
-            .user_id("tyrid")
-            .resource_id("ocean-view-room-714")
-            .start("2022-12-20T15:00:00-0700".parse::<Timestamp>().unwrap())
-            .end("2022-12-28T12:00:00-0700".parse::<Timestamp>().unwrap())
-            .status(ReservationStatus::Pending as i32)
+        sqlx::query("UPDATE rsvp.resources SET pre_buffer = '15 minutes', post_buffer = '30 minutes' WHERE id = 'ocean-view-room-715'")
+            .execute(&migrated_pool)
+            .await
+            .unwrap();
+        let rsvp = |start: &str, end: &str| {
+            abi::Reservation::new_pending(
+                "tyrid",
+                "ocean-view-room-715",
+                start.parse().unwrap(),
+                end.parse().unwrap(),
+                "hello.",
+            )
+        };
+
+        let first = rsvp("2023-01-03T10:00:00+0000", "2023-01-03T11:00:00+0000");
+        let first = manager.reserve(first).await.unwrap();
+        // the customer's window is kept, the buffers only count for conflicts
+        assert_eq!(first.start.unwrap().to_string(), "2023-01-03T10:00:00Z");
+        assert_eq!(first.end.unwrap().to_string(), "2023-01-03T11:00:00Z");
+
+        // its pre buffer overlaps the post buffer of the first one
+        let err = manager
+            .reserve(rsvp("2023-01-03T11:30:00+0000", "2023-01-03T12:00:00+0000"))
+            .await
+            .unwrap_err();
+        if let abi::Error::ConflictReservation(ReservationConflictInfo::Parsed(info)) = err {
+            // the booked windows are reported, along with the buffered ones in the way
+            assert_eq!(info.old.start.to_rfc3339(), "2023-01-03T10:00:00+00:00");
+            assert_eq!(info.old.end.to_rfc3339(), "2023-01-03T11:00:00+00:00");
+            let buffered = info.old.buffered.unwrap();
+            assert_eq!(buffered.start.to_rfc3339(), "2023-01-03T09:45:00+00:00");
+            assert_eq!(buffered.end.to_rfc3339(), "2023-01-03T11:30:00+00:00");
+            assert_eq!(info.new.start.to_rfc3339(), "2023-01-03T11:30:00+00:00");
+        } else {
+            panic!("expect conflict reservation error, got {:?}", err);
+        }
+        manager
+            .reserve(rsvp("2023-01-03T11:45:00+0000", "2023-01-03T12:30:00+0000"))
+            .await
+            .unwrap();
+
+        let slots = manager
+            .availability(
+                "ocean-view-room-715".into(),
+                "2023-01-03T08:00:00Z".parse().unwrap().."2023-01-03T14:00:00Z".parse().unwrap(),
+                Duration::zero(),
+                Duration::zero(),
+            )
+            .await
+            .unwrap();
+        let slots: Vec<_> = slots
+            .into_iter()
+            .map(|slot| {
+                (
+                    slot.start.unwrap().to_string(),
+                    slot.end.unwrap().to_string(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            slots,
+            vec![
+                (
+                    "2023-01-03T08:00:00Z".to_string(),
+                    "2023-01-03T09:15:00Z".to_string()
+                ),
+                (
+                    "2023-01-03T13:15:00Z".to_string(),
+                    "2023-01-03T14:00:00Z".to_string()
+                ),
+            ]
+        );
+    }
+
+    fn shared_desk(start: &str, end: &str) -> abi::Reservation {
+        abi::Reservation::new_pending(
+            "tyrid",
+            "ocean-view-room-716",
+            start.parse().unwrap(),
+            end.parse().unwrap(),
+            "hello.",
+        )
+    }
+
+    async fn set_capacity(pool: &Pool<Postgres>, capacity: i32) {
+        sqlx::query("UPDATE rsvp.resources SET capacity = $1 WHERE id = 'ocean-view-room-716'")
+            .bind(capacity)
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+
+    fn capacity_conflict(err: abi::Error) -> abi::CapacityConflict {
+        match err {
+            abi::Error::ConflictReservation(ReservationConflictInfo::Capacity(info)) => info,
+            err => panic!("expect capacity conflict, got {:?}", err),
+        }
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn reserve_should_respect_capacity(migrated_pool: Pool<Postgres>) {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        set_capacity(&migrated_pool, 2).await;
+
+        let first = shared_desk("2023-01-02T10:00:00Z", "2023-01-02T12:00:00Z");
+        let first = manager.reserve(first).await.unwrap();
+        let second = shared_desk("2023-01-02T11:00:00Z", "2023-01-02T13:00:00Z");
+        let second = manager.reserve(second).await.unwrap();
+        // overlaps the first one only, the second one starts after it ends
+        let early = shared_desk("2023-01-02T09:00:00Z", "2023-01-02T10:30:00Z");
+        let early = manager.reserve(early).await.unwrap();
+
+        let third = shared_desk("2023-01-02T11:30:00Z", "2023-01-02T12:30:00Z");
+        let err = manager.reserve(third.clone()).await.unwrap_err();
+        let info = capacity_conflict(err);
+        assert_eq!(info.capacity, 2);
+        let ids: Vec<_> = info.overlapping.into_iter().map(|r| r.id).collect();
+        assert_eq!(ids, vec![first.id.clone(), second.id.clone()]);
+
+        // moving into the full window is rejected as well
+        let err = manager
+            .reschedule(
+                early.id.clone(),
+                "2023-01-02T11:30:00Z".parse().unwrap(),
+                "2023-01-02T12:30:00Z".parse().unwrap(),
+                None,
+                None,
+            )
+            .await
+            .unwrap_err();
+        capacity_conflict(err);
+
+        manager.cancel(first.id, None).await.unwrap();
+        manager.reserve(third).await.unwrap();
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn reserve_should_count_buffers_against_capacity(migrated_pool: Pool<Postgres>) {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        set_capacity(&migrated_pool, 2).await;
+        sqlx::query(
+            "UPDATE rsvp.resources SET post_buffer = '30 minutes' WHERE id = 'ocean-view-room-716'",
+        )
+        .execute(&migrated_pool)
+        .await
+        .unwrap();
+
+        let first = shared_desk("2023-01-02T10:00:00Z", "2023-01-02T11:00:00Z");
+        let first = manager.reserve(first).await.unwrap();
+        let second = shared_desk("2023-01-02T10:15:00Z", "2023-01-02T11:00:00Z");
+        let second = manager.reserve(second).await.unwrap();
+
+        // within the post buffers of both
+        let err = manager
+            .reserve(shared_desk("2023-01-02T11:15:00Z", "2023-01-02T12:00:00Z"))
+            .await
+            .unwrap_err();
+        let ids: Vec<_> = capacity_conflict(err)
+            .overlapping
+            .into_iter()
+            .map(|r| r.id)
+            .collect();
+        assert_eq!(ids, vec![first.id, second.id]);
+
+        manager
+            .reserve(shared_desk("2023-01-02T11:30:00Z", "2023-01-02T12:00:00Z"))
+            .await
+            .unwrap();
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn concurrent_reserve_should_not_exceed_capacity(migrated_pool: Pool<Postgres>) {
+        set_capacity(&migrated_pool, 2).await;
+
+        let attempts = (0..6).map(|_| {
+            let manager = ReservationManager::new(migrated_pool.clone());
+            tokio::spawn(async move {
+                let rsvp = shared_desk("2023-01-02T10:00:00Z", "2023-01-02T12:00:00Z");
+                manager.reserve(rsvp).await
+            })
+        });
+        let results = futures::future::join_all(attempts).await;
+
+        let made = results
+            .into_iter()
+            .filter(|result| result.as_ref().unwrap().is_ok())
+            .count();
+        assert_eq!(made, 2);
+    }
+
+    fn new_hold(ttl: std::time::Duration) -> abi::Reservation {
+        let mut rsvp = abi::Reservation::new_pending(
+            "tyrid",
+            "ocean-view-room-713",
+            "2022-12-25T15:00:00-0700".parse().unwrap(),
+            "2022-12-28T12:00:00-0700".parse().unwrap(),
+            "hello.",
+        );
+        rsvp.hold_ttl = Some(ttl.try_into().unwrap());
+        rsvp
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn reserve_with_hold_ttl_should_set_expiry(migrated_pool: Pool<Postgres>) {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let rsvp = manager
+            .reserve(new_hold(std::time::Duration::from_secs(600)))
+            .await
+            .unwrap();
+        let expires_at = rsvp.expires_at.clone().unwrap();
+        assert_eq!(
+            manager.get(rsvp.id.clone()).await.unwrap().expires_at,
+            Some(expires_at)
+        );
+
+        // a confirmed reservation is no longer a hold
+        let rsvp = manager.change_status(rsvp.id, None).await.unwrap();
+        assert_eq!(rsvp.expires_at, None);
+
+        let mut rsvp = new_hold(std::time::Duration::ZERO);
+        rsvp.resource_id = "ocean-view-room-714".into();
+        let err = manager.reserve(rsvp).await.unwrap_err();
+        assert_eq!(err, abi::Error::InvalidHoldTtl);
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn confirm_expired_hold_should_reject(migrated_pool: Pool<Postgres>) {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let rsvp = manager
+            .reserve(new_hold(std::time::Duration::from_millis(10)))
+            .await
+            .unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+
+        let err = manager
+            .change_status(rsvp.id.clone(), None)
+            .await
+            .unwrap_err();
+        assert_eq!(err, abi::Error::HoldExpired);
+
+        // still expired once the hold is cancelled, and the slot is free again
+        let expired = manager.expire_holds().await.unwrap();
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].status, ReservationStatus::Cancelled as i32);
+        let err = manager.change_status(rsvp.id, None).await.unwrap_err();
+        assert_eq!(err, abi::Error::HoldExpired);
+        manager
+            .reserve(new_hold(std::time::Duration::from_secs(600)))
+            .await
+            .unwrap();
+        assert!(manager.expire_holds().await.unwrap().is_empty());
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn reserve_with_same_key_should_return_original(migrated_pool: Pool<Postgres>) {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let rsvp = abi::Reservation::new_pending(
+            "tyrid",
+            "ocean-view-room-713",
+            "2022-12-25T15:00:00-0700".parse().unwrap(),
+            "2022-12-28T12:00:00-0700".parse().unwrap(),
+            "hello.",
+        );
+
+        let first = manager
+            .reserve_idempotent("key-1".into(), rsvp.clone())
+            .await
+            .unwrap();
+        let retried = manager
+            .reserve_idempotent("key-1".into(), rsvp.clone())
+            .await
+            .unwrap();
+        assert_eq!(retried, first);
+
+        // the same key can't be used for another request
+        let err = manager
+            .reserve_idempotent(
+                "key-1".into(),
+                abi::Reservation {
+                    note: "another note".into(),
+                    ..rsvp.clone()
+                },
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(err, abi::Error::IdempotencyKeyReused("key-1".into()));
+
+        // another key is another call, which conflicts with the first one
+        let err = manager
+            .reserve_idempotent("key-2".into(), rsvp)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, abi::Error::ConflictReservation(_)));
+
+        let confirmed = manager
+            .change_status_idempotent("key-1".into(), first.id.clone(), None)
+            .await
+            .unwrap();
+        let retried = manager
+            .change_status_idempotent("key-1".into(), first.id, None)
+            .await
+            .unwrap();
+        assert_eq!(retried, confirmed);
+
+        let other = manager
+            .reserve(abi::Reservation::new_pending(
+                "aliceid",
+                "ocean-view-room-714",
+                "2022-12-25T15:00:00-0700".parse().unwrap(),
+                "2022-12-28T12:00:00-0700".parse().unwrap(),
+                "hello.",
+            ))
+            .await
+            .unwrap();
+        let err = manager
+            .change_status_idempotent("key-1".into(), other.id, None)
+            .await
+            .unwrap_err();
+        assert_eq!(err, abi::Error::IdempotencyKeyReused("key-1".into()));
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn reserve_with_expired_key_should_run_again(migrated_pool: Pool<Postgres>) {
+        let manager = ReservationManager::new(migrated_pool.clone())
+            .with_idempotency_window(std::time::Duration::ZERO);
+        let rsvp = abi::Reservation::new_pending(
+            "tyrid",
+            "ocean-view-room-713",
+            "2022-12-25T15:00:00-0700".parse().unwrap(),
+            "2022-12-28T12:00:00-0700".parse().unwrap(),
+            "hello.",
+        );
+
+        manager
+            .reserve_idempotent("key-1".into(), rsvp.clone())
+            .await
+            .unwrap();
+        let err = manager
+            .reserve_idempotent("key-1".into(), rsvp)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, abi::Error::ConflictReservation(_)));
+    }
+
+    fn batch() -> Vec<abi::Reservation> {
+        let new_rsvp = |uid: &str, rid: &str| {
+            abi::Reservation::new_pending(
+                uid,
+                rid,
+                "2022-12-25T15:00:00-0700".parse().unwrap(),
+                "2022-12-28T12:00:00-0700".parse().unwrap(),
+                "conference.",
+            )
+        };
+        vec![
+            new_rsvp("tyrid", "ocean-view-room-713"),
+            // conflicts with the existing reservation
+            new_rsvp("tyrid", "ocean-view-room-714"),
+            new_rsvp("tyrid", "ocean-view-room-715"),
+            // conflicts with the first one in the batch
+            new_rsvp("aliceid", "ocean-view-room-713"),
+            new_rsvp("", "ocean-view-room-716"),
+        ]
+    }
+
+    fn assert_conflict(result: &Result<abi::Reservation, abi::Error>, rid: &str) {
+        match result {
+            Err(abi::Error::ConflictReservation(ReservationConflictInfo::Parsed(info))) => {
+                assert_eq!(info.old.rid, rid)
+            }
+            _ => panic!("expect conflict reservation error, got {:?}", result),
+        }
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn reserve_batch_atomic_should_report_all_failures(migrated_pool: Pool<Postgres>) {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let existing = manager.reserve(batch().remove(1)).await.unwrap();
+
+        let results = manager
+            .reserve_batch(batch(), abi::BatchMode::Atomic)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 5);
+        assert_eq!(results[0], Err(abi::Error::BatchAborted));
+        assert_conflict(&results[1], "ocean-view-room-714");
+        assert_eq!(results[2], Err(abi::Error::BatchAborted));
+        assert_conflict(&results[3], "ocean-view-room-713");
+        assert_eq!(results[4], Err(abi::Error::InvalidUserId("".into())));
+
+        // nothing but the existing reservation was stored
+        let query = abi::ReservationQueryBuilder::default().build().unwrap();
+        assert_eq!(manager.query(query).await.unwrap(), vec![existing]);
+
+        let results = manager
+            .reserve_batch(batch()[2..4].to_vec(), abi::BatchMode::Atomic)
+            .await
+            .unwrap();
+        assert!(results.iter().all(|result| result.is_ok()));
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn reserve_batch_best_effort_should_keep_successes(migrated_pool: Pool<Postgres>) {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        manager.reserve(batch().remove(1)).await.unwrap();
+
+        let results = manager
+            .reserve_batch(batch(), abi::BatchMode::BestEffort)
+            .await
+            .unwrap();
+        let made: Vec<_> = results.iter().filter_map(|r| r.as_ref().ok()).collect();
+        assert_eq!(made.len(), 2);
+        assert_conflict(&results[1], "ocean-view-room-714");
+        assert_conflict(&results[3], "ocean-view-room-713");
+
+        for rsvp in made {
+            assert_eq!(&manager.get(rsvp.id.clone()).await.unwrap(), rsvp);
+        }
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn reservation_change_status_should_work(migrated_pool: Pool<Postgres>) {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let rsvp = abi::Reservation::new_pending(
+            "tyrid",
+            "ocean-view-room-714",
+            "2022-12-25T15:00:00-0700".parse().unwrap(),
+            "2022-12-28T12:00:00-0700".parse().unwrap(),
+            "I'll arrive at 3pm. Please help to upgrade to execuitive room if possible.",
+        );
+
+        let rsvp = manager.reserve(rsvp).await.unwrap();
+        let rsvp = manager.change_status(rsvp.id, None).await.unwrap();
+        assert_eq!(rsvp.status, ReservationStatus::Confirmed as i32);
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn reservation_change_status_not_pending_should_reject(migrated_pool: Pool<Postgres>) {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let rsvp = abi::Reservation::new_pending(
+            "tyrid",
+            "ocean-view-room-714",
+            "2022-12-25T15:00:00-0700".parse().unwrap(),
+            "2022-12-28T12:00:00-0700".parse().unwrap(),
+            "I'll arrive at 3pm. Please help to upgrade to execuitive room if possible.",
+        );
+
+        let rsvp = manager.reserve(rsvp).await.unwrap();
+        let rsvp = manager.change_status(rsvp.id, None).await.unwrap();
+
+        // change status again is not a valid transition
+        let err = manager.change_status(rsvp.id, None).await.unwrap_err();
+        assert_eq!(
+            err,
+            abi::Error::InvalidStatusTransition {
+                from: ReservationStatus::Confirmed,
+                to: ReservationStatus::Confirmed,
+            }
+        );
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn transition_should_follow_status_table(migrated_pool: Pool<Postgres>) {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let rsvp = abi::Reservation::new_pending(
+            "tyrid",
+            "ocean-view-room-714",
+            "2022-12-25T15:00:00-0700".parse().unwrap(),
+            "2022-12-28T12:00:00-0700".parse().unwrap(),
+            "hello.",
+        );
+        let pending = manager.reserve(rsvp.clone()).await.unwrap();
+
+        // a booking can't be turned into a block
+        let err = manager
+            .transition(pending.id.clone(), ReservationStatus::Blocked, None)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err,
+            abi::Error::InvalidStatusTransition {
+                from: ReservationStatus::Pending,
+                to: ReservationStatus::Blocked,
+            }
+        );
+        manager.cancel(pending.id, None).await.unwrap();
+
+        let mut blocked = rsvp;
+        blocked.status = ReservationStatus::Blocked as i32;
+        let rsvp = manager.reserve(blocked).await.unwrap();
+        assert_eq!(rsvp.status, ReservationStatus::Blocked as i32);
+
+        let err = manager
+            .transition(rsvp.id.clone(), ReservationStatus::Confirmed, None)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err,
+            abi::Error::InvalidStatusTransition {
+                from: ReservationStatus::Blocked,
+                to: ReservationStatus::Confirmed,
+            }
+        );
+
+        let rsvp = manager
+            .transition(rsvp.id, ReservationStatus::Released, None)
+            .await
+            .unwrap();
+        assert_eq!(rsvp.status, ReservationStatus::Released as i32);
+        assert_eq!(manager.get(rsvp.id.clone()).await.unwrap(), rsvp);
+
+        let err = manager
+            .transition(
+                "00000000-0000-0000-0000-000000000000".into(),
+                ReservationStatus::Released,
+                None,
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(err, abi::Error::NotFound);
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn update_should_only_change_masked_fields(migrated_pool: Pool<Postgres>) {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let rsvp = abi::Reservation::new_pending(
+            "tyrid",
+            "ocean-view-room-714",
+            "2022-12-25T15:00:00-0700".parse().unwrap(),
+            "2022-12-28T12:00:00-0700".parse().unwrap(),
+            "hello.",
+        );
+        let rsvp = manager.reserve(rsvp).await.unwrap();
+
+        let changes = abi::Reservation {
+            user_id: "aliceid".into(),
+            note: "ignored.".into(),
+            resource_id: "ignored".into(),
+            ..Default::default()
+        };
+        let request = abi::UpdateRequest {
+            id: rsvp.id.clone(),
+            field_mask: Some(prost_types::FieldMask {
+                paths: vec!["user_id".into()],
+            }),
+            reservation: Some(changes.clone()),
+            ..Default::default()
+        };
+        let updated = manager.update(request).await.unwrap();
+        assert_eq!(updated.user_id, "aliceid");
+        assert_eq!(updated.note, "hello.");
+        assert_eq!(updated.resource_id, rsvp.resource_id);
+
+        let request = abi::UpdateRequest {
+            id: rsvp.id.clone(),
+            field_mask: Some(prost_types::FieldMask {
+                paths: vec!["note".into(), "user_id".into()],
+            }),
+            reservation: Some(abi::Reservation {
+                user_id: "bobid".into(),
+                ..changes
+            }),
+            ..Default::default()
+        };
+        let updated = manager.update(request).await.unwrap();
+        assert_eq!(updated.user_id, "bobid");
+        assert_eq!(updated.note, "ignored.");
+        assert_eq!(manager.get(rsvp.id).await.unwrap(), updated);
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn reschedule_should_move_reservation(migrated_pool: Pool<Postgres>) {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let rsvp = abi::Reservation::new_pending(
+            "tyrid",
+            "ocean-view-room-713",
+            "2022-12-25T15:00:00-0700".parse().unwrap(),
+            "2022-12-28T12:00:00-0700".parse().unwrap(),
+            "hello.",
+        );
+        let rsvp = manager.reserve(rsvp).await.unwrap();
+
+        // overlapping its own window is fine
+        let start: DateTime<Utc> = "2022-12-26T22:00:00Z".parse().unwrap();
+        let end: DateTime<Utc> = "2022-12-29T19:00:00Z".parse().unwrap();
+        let moved = manager
+            .reschedule(rsvp.id.clone(), start, end, None, None)
+            .await
+            .unwrap();
+        assert_eq!(moved.resource_id, "ocean-view-room-713");
+        assert_eq!(moved.start.unwrap().seconds, start.timestamp());
+        assert_eq!(moved.end.unwrap().seconds, end.timestamp());
+
+        let moved = manager
+            .reschedule(
+                rsvp.id.clone(),
+                start,
+                end,
+                Some("ocean-view-room-714".into()),
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(moved.resource_id, "ocean-view-room-714");
+        assert_eq!(manager.get(rsvp.id.clone()).await.unwrap(), moved);
+
+        let err = manager
+            .reschedule(rsvp.id, end, start, None, None)
+            .await
+            .unwrap_err();
+        assert_eq!(err, abi::Error::InvalidTime);
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn reschedule_conflict_should_keep_original(migrated_pool: Pool<Postgres>) {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let rsvps = make_reservations(&manager, 2).await;
+
+        // move the first reservation onto the second one
+        let start = rsvps[1].start.clone().unwrap();
+        let end = rsvps[1].end.clone().unwrap();
+        let err = manager
+            .reschedule(
+                rsvps[0].id.clone(),
+                DateTime::from_timestamp(start.seconds, 0).unwrap(),
+                DateTime::from_timestamp(end.seconds, 0).unwrap(),
+                None,
+                None,
+            )
+            .await
+            .unwrap_err();
+
+        if let abi::Error::ConflictReservation(ReservationConflictInfo::Parsed(info)) = err {
+            assert_eq!(info.old.rid, "ocean-view-room-714");
+            assert_eq!(info.old.start.timestamp(), start.seconds);
+        } else {
+            panic!("expect conflict reservation error, got {:?}", err);
+        }
+        assert_eq!(manager.get(rsvps[0].id.clone()).await.unwrap(), rsvps[0]);
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn stale_version_should_be_rejected(migrated_pool: Pool<Postgres>) {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let rsvp = abi::Reservation::new_pending(
+            "tyrid",
+            "ocean-view-room-714",
+            "2022-12-25T15:00:00-0700".parse().unwrap(),
+            "2022-12-28T12:00:00-0700".parse().unwrap(),
+            "hello.",
+        );
+        let rsvp = manager.reserve(rsvp).await.unwrap();
+        assert_eq!(rsvp.version, 1);
+
+        let updated = manager
+            .update_note(rsvp.id.clone(), "first desk.".into(), Some(1))
+            .await
+            .unwrap();
+        assert_eq!(updated.version, 2);
+
+        // the second desk still works on version 1
+        let stale = abi::Error::VersionMismatch { current: 2 };
+        let err = manager
+            .update_note(rsvp.id.clone(), "second desk.".into(), Some(1))
+            .await
+            .unwrap_err();
+        assert_eq!(err, stale);
+        let request = abi::UpdateRequest {
+            id: rsvp.id.clone(),
+            field_mask: Some(prost_types::FieldMask {
+                paths: vec!["note".into()],
+            }),
+            reservation: Some(rsvp.clone()),
+            expected_version: Some(1),
+        };
+        assert_eq!(manager.update(request).await.unwrap_err(), stale);
+        let start = DateTime::from_timestamp(rsvp.start.clone().unwrap().seconds, 0).unwrap();
+        let end = DateTime::from_timestamp(rsvp.end.clone().unwrap().seconds, 0).unwrap();
+        let err = manager
+            .reschedule(rsvp.id.clone(), start, end, None, Some(1))
+            .await
+            .unwrap_err();
+        assert_eq!(err, stale);
+        let err = manager
+            .change_status(rsvp.id.clone(), Some(1))
+            .await
+            .unwrap_err();
+        assert_eq!(err, stale);
+        let err = manager.delete(rsvp.id.clone(), Some(1)).await.unwrap_err();
+        assert_eq!(err, stale);
+        assert_eq!(manager.get(rsvp.id.clone()).await.unwrap(), updated);
+
+        let confirmed = manager
+            .change_status(rsvp.id.clone(), Some(2))
+            .await
+            .unwrap();
+        assert_eq!(confirmed.version, 3);
+        manager.delete(rsvp.id.clone(), Some(3)).await.unwrap();
+        let err = manager.cancel(rsvp.id, Some(3)).await.unwrap_err();
+        assert_eq!(err, abi::Error::NotFound);
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn availability_should_return_free_slots(migrated_pool: Pool<Postgres>) {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        // 10:00-12:00 each day from 2023-01-01, in -0700
+        let rsvps = make_reservations(&manager, 3).await;
+        manager.cancel(rsvps[1].id.clone(), None).await.unwrap();
+
+        let window: Range<DateTime<Utc>> =
+            "2023-01-01T00:00:00Z".parse().unwrap().."2023-01-04T00:00:00Z".parse().unwrap();
+        let slots = manager
+            .availability(
+                "ocean-view-room-714".into(),
+                window.clone(),
+                chrono::Duration::zero(),
+                chrono::Duration::zero(),
+            )
+            .await
+            .unwrap();
+        let slot = |start: &str, end: &str| {
+            abi::TimeSlot::new(start.parse().unwrap(), end.parse().unwrap())
+        };
+        // the cancelled reservation of the second day frees its slot
+        assert_eq!(
+            slots,
+            vec![
+                slot("2023-01-01T00:00:00Z", "2023-01-01T17:00:00Z"),
+                slot("2023-01-01T19:00:00Z", "2023-01-03T17:00:00Z"),
+                slot("2023-01-03T19:00:00Z", "2023-01-04T00:00:00Z"),
+            ]
+        );
+
+        let slots = manager
+            .availability(
+                "ocean-view-room-714".into(),
+                window.clone(),
+                chrono::Duration::hours(6),
+                chrono::Duration::hours(4),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            slots,
+            vec![
+                slot("2023-01-01T00:00:00Z", "2023-01-01T16:00:00Z"),
+                slot("2023-01-01T20:00:00Z", "2023-01-03T16:00:00Z"),
+            ]
+        );
+
+        let err = manager
+            .availability(
+                "ocean-view-room-714".into(),
+                window.end..window.start,
+                chrono::Duration::zero(),
+                chrono::Duration::zero(),
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(err, abi::Error::InvalidTime);
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn availability_should_leave_shared_resources_free_until_full(
+        migrated_pool: Pool<Postgres>,
+    ) {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        set_capacity(&migrated_pool, 2).await;
+        let window: Range<DateTime<Utc>> =
+            "2023-01-02T09:00:00Z".parse().unwrap().."2023-01-02T12:00:00Z".parse().unwrap();
+        let slots = || {
+            manager.availability(
+                "ocean-view-room-716".into(),
+                window.clone(),
+                chrono::Duration::zero(),
+                chrono::Duration::zero(),
+            )
+        };
+        let slot = |start: &str, end: &str| {
+            abi::TimeSlot::new(start.parse().unwrap(), end.parse().unwrap())
+        };
+
+        manager
+            .reserve(shared_desk("2023-01-02T10:00:00Z", "2023-01-02T11:00:00Z"))
+            .await
+            .unwrap();
+        // one seat left
+        assert_eq!(
+            slots().await.unwrap(),
+            vec![slot("2023-01-02T09:00:00Z", "2023-01-02T12:00:00Z")]
+        );
+
+        manager
+            .reserve(shared_desk("2023-01-02T10:30:00Z", "2023-01-02T11:30:00Z"))
+            .await
+            .unwrap();
+        assert_eq!(
+            slots().await.unwrap(),
+            vec![
+                slot("2023-01-02T09:00:00Z", "2023-01-02T10:30:00Z"),
+                slot("2023-01-02T11:00:00Z", "2023-01-02T12:00:00Z"),
+            ]
+        );
+        // the gaps can be reserved as they are
+        manager
+            .reserve(shared_desk("2023-01-02T09:00:00Z", "2023-01-02T10:30:00Z"))
+            .await
+            .unwrap();
+        manager
+            .reserve(shared_desk("2023-01-02T11:00:00Z", "2023-01-02T12:00:00Z"))
+            .await
+            .unwrap();
+    }
+
+    #[test]
+    fn fit_slot_should_round_inwards() {
+        let gap = |start: &str, end: &str| -> Range<DateTime<Utc>> {
+            start.parse().unwrap()..end.parse().unwrap()
+        };
+        let quarter = chrono::Duration::minutes(15);
+        assert_eq!(
+            fit_slot(
+                gap("2023-01-01T10:05:00Z", "2023-01-01T11:10:00Z"),
+                chrono::Duration::zero(),
+                quarter
+            ),
+            Some(gap("2023-01-01T10:15:00Z", "2023-01-01T11:00:00Z"))
+        );
+        assert_eq!(
+            fit_slot(
+                gap("2023-01-01T10:05:00Z", "2023-01-01T10:20:00Z"),
+                chrono::Duration::zero(),
+                quarter
+            ),
+            None
+        );
+        assert_eq!(
+            fit_slot(
+                gap("2023-01-01T10:00:00Z", "2023-01-01T10:30:00Z"),
+                chrono::Duration::hours(1),
+                chrono::Duration::zero()
+            ),
+            None
+        );
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn update_note_should_work(migrated_pool: Pool<Postgres>) {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let rsvp = abi::Reservation::new_pending(
+            "tyrid",
+            "ocean-view-room-714",
+            "2022-12-25T15:00:00-0700".parse().unwrap(),
+            "2022-12-28T12:00:00-0700".parse().unwrap(),
+            "I'll arrive at 3pm. Please help to upgrade to execuitive room if possible.",
+        );
+
+        let rsvp = manager.reserve(rsvp).await.unwrap();
+        let rsvp = manager
+            .update_note(rsvp.id, "I'll arrive at 4pm.".to_string(), None)
+            .await
+            .unwrap();
+
+        assert_eq!(rsvp.note, "I'll arrive at 4pm.");
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn get_reservation_should_work(migrated_pool: Pool<Postgres>) {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let rsvp = abi::Reservation::new_pending(
+            "tyrid",
+            "ocean-view-room-714",
+            "2022-12-25T15:00:00-0700".parse().unwrap(),
+            "2022-12-28T12:00:00-0700".parse().unwrap(),
+            "I'll arrive at 3pm. Please help to upgrade to execuitive room if possible.",
+        );
+
+        let rsvp = manager.reserve(rsvp).await.unwrap();
+        let rsvp = manager.get(rsvp.id).await.unwrap();
+
+        assert_eq!(rsvp.user_id, "tyrid");
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn delete_reservation_should_work(migrated_pool: Pool<Postgres>) {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let rsvp = abi::Reservation::new_pending(
+            "tyrid",
+            "ocean-view-room-714",
+            "2022-12-25T15:00:00-0700".parse().unwrap(),
+            "2022-12-28T12:00:00-0700".parse().unwrap(),
+            "I'll arrive at 3pm. Please help to upgrade to execuitive room if possible.",
+        );
+
+        let rsvp = manager.reserve(rsvp).await.unwrap();
+        manager.delete(rsvp.id, None).await.unwrap();
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn cancel_reservation_should_keep_record_and_free_slot(migrated_pool: Pool<Postgres>) {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let rsvp = abi::Reservation::new_pending(
+            "tyrid",
+            "ocean-view-room-714",
+            "2022-12-25T15:00:00-0700".parse().unwrap(),
+            "2022-12-28T12:00:00-0700".parse().unwrap(),
+            "hello.",
+        );
+
+        let rsvp = manager.reserve(rsvp.clone()).await.unwrap();
+        let cancelled = manager.cancel(rsvp.id.clone(), None).await.unwrap();
+        assert_eq!(cancelled.status, ReservationStatus::Cancelled as i32);
+        assert_eq!(manager.get(rsvp.id.clone()).await.unwrap(), cancelled);
+
+        // the same slot can be reserved again
+        let mut other = rsvp.clone();
+        other.id = String::new();
+        other.user_id = "aliceid".into();
+        manager.reserve(other).await.unwrap();
+
+        let err = manager
+            .cancel("00000000-0000-0000-0000-000000000000".into(), None)
+            .await
+            .unwrap_err();
+        assert_eq!(err, abi::Error::NotFound);
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn query_reservation_should_work(migrated_pool: Pool<Postgres>) {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let query = abi::ReservationQueryBuilder::default()
+            .user_id("tyrid")
+            .resource_id("ocean-view-room-714")
+            .start("2022-12-20T15:00:00-0700".parse::<Timestamp>().unwrap())
+            .end("2022-12-28T12:00:00-0700".parse::<Timestamp>().unwrap())
+            .status(ReservationStatus::Pending as i32)
             .build()
             .unwrap();
         let rsvp = abi::Reservation::new_pending(
@@ -278,4 +1990,448 @@ mod tests {
         assert_eq!(rsvps.len(), 1);
         assert_eq!(rsvps[0], rsvp)
     }
+
+    async fn make_reservations_in_each_status(
+        manager: &ReservationManager,
+    ) -> Vec<abi::Reservation> {
+        let mut rsvps = vec![];
+        for (i, status) in [
+            ReservationStatus::Pending,
+            ReservationStatus::Confirmed,
+            ReservationStatus::Blocked,
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let mut rsvp = abi::Reservation::new_pending(
+                "tyrid",
+                format!("ocean-view-room-{}", 713 + i),
+                "2022-12-25T15:00:00-0700".parse().unwrap(),
+                "2022-12-28T12:00:00-0700".parse().unwrap(),
+                "hello.",
+            );
+            rsvp.status = status as i32;
+            rsvps.push(manager.reserve(rsvp).await.unwrap());
+        }
+        rsvps
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn query_unknown_status_should_return_all(migrated_pool: Pool<Postgres>) {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let rsvps = make_reservations_in_each_status(&manager).await;
+
+        let query = abi::ReservationQueryBuilder::default()
+            .user_id("tyrid")
+            .start("2022-12-20T15:00:00-0700".parse::<Timestamp>().unwrap())
+            .end("2022-12-30T12:00:00-0700".parse::<Timestamp>().unwrap())
+            .status(ReservationStatus::Unknown as i32)
+            .build()
+            .unwrap();
+        let mut result = manager.query(query).await.unwrap();
+        result.sort_by(|a, b| a.resource_id.cmp(&b.resource_id));
+
+        assert_eq!(result, rsvps);
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn query_status_set_should_match_any_of_them(migrated_pool: Pool<Postgres>) {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let rsvps = make_reservations_in_each_status(&manager).await;
+
+        let query = abi::ReservationQueryBuilder::default()
+            .user_id("tyrid")
+            .start("2022-12-20T15:00:00-0700".parse::<Timestamp>().unwrap())
+            .end("2022-12-30T12:00:00-0700".parse::<Timestamp>().unwrap())
+            .statuses(vec![
+                ReservationStatus::Pending as i32,
+                ReservationStatus::Confirmed as i32,
+            ])
+            .build()
+            .unwrap();
+        let mut result = manager.query(query).await.unwrap();
+        result.sort_by(|a, b| a.resource_id.cmp(&b.resource_id));
+
+        assert_eq!(result, rsvps[0..2]);
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn filter_status_set_should_match_any_of_them(migrated_pool: Pool<Postgres>) {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let rsvps = make_reservations_in_each_status(&manager).await;
+
+        let filter = abi::ReservationFilterBuilder::default()
+            .user_id("tyrid")
+            .status(ReservationStatus::Blocked as i32)
+            .statuses(vec![ReservationStatus::Pending as i32])
+            .build()
+            .unwrap();
+        let (_, mut result) = manager.filter(filter).await.unwrap();
+        result.sort_by(|a, b| a.resource_id.cmp(&b.resource_id));
+
+        assert_eq!(result, vec![rsvps[0].clone(), rsvps[2].clone()]);
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn query_open_ended_window_should_work(migrated_pool: Pool<Postgres>) {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let rsvps = make_reservations(&manager, 3).await;
+
+        let query = abi::ReservationQueryBuilder::default()
+            .user_id("tyrid")
+            .build()
+            .unwrap();
+        assert_eq!(manager.query(query).await.unwrap(), rsvps);
+
+        // everything starting from the second reservation
+        let query = abi::ReservationQueryBuilder::default()
+            .user_id("tyrid")
+            .start(rsvps[1].start.clone().unwrap())
+            .build()
+            .unwrap();
+        assert_eq!(manager.query(query).await.unwrap(), rsvps[1..]);
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn query_page_should_count_all_pages(migrated_pool: Pool<Postgres>) {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        make_reservations(&manager, 25).await;
+
+        let query = abi::ReservationQueryBuilder::default()
+            .user_id("tyrid")
+            .page(3)
+            .page_size(10)
+            .build()
+            .unwrap();
+        let (pager, rows) = manager.query_page(query).await.unwrap();
+        assert_eq!(
+            pager,
+            abi::QueryPager {
+                total: 25,
+                page: 3,
+                page_size: 10,
+                has_next: false,
+            }
+        );
+        let rows: Vec<_> = rows.try_collect().await.unwrap();
+        assert_eq!(rows.len(), 5);
+
+        let query = abi::ReservationQueryBuilder::default()
+            .user_id("tyrid")
+            .page(2)
+            .page_size(10)
+            .build()
+            .unwrap();
+        let (pager, _) = manager.query_page(query).await.unwrap();
+        assert_eq!(pager.total, 25);
+        assert!(pager.has_next);
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn query_should_clamp_page_size(migrated_pool: Pool<Postgres>) {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        make_reservations(&manager, 25).await;
+
+        let query = abi::ReservationQueryBuilder::default()
+            .user_id("tyrid")
+            .page_size(1000)
+            .build()
+            .unwrap();
+        let (pager, rows) = manager.query_page(query).await.unwrap();
+        let rows: Vec<_> = rows.try_collect().await.unwrap();
+        assert_eq!(pager.page_size, 100);
+        assert_eq!(rows.len(), 25);
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn query_page_should_count_the_rows_it_returns(migrated_pool: Pool<Postgres>) {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        make_reservations(&manager, 5).await;
+
+        let query = abi::ReservationQueryBuilder::default()
+            .user_id("tyrid")
+            .page_size(10)
+            .build()
+            .unwrap();
+        let (pager, rows) = manager.query_page(query).await.unwrap();
+        // made after the count, before the rows are read
+        manager
+            .reserve(abi::Reservation::new_pending(
+                "tyrid",
+                "ocean-view-room-713",
+                "2023-01-01T10:00:00-0700".parse().unwrap(),
+                "2023-01-01T12:00:00-0700".parse().unwrap(),
+                "hello.",
+            ))
+            .await
+            .unwrap();
+
+        let rows: Vec<_> = rows.try_collect().await.unwrap();
+        assert_eq!(pager.total, 5);
+        assert_eq!(rows.len(), 5);
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn query_inverted_window_should_reject(migrated_pool: Pool<Postgres>) {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let query = abi::ReservationQueryBuilder::default()
+            .start("2022-12-28T12:00:00-0700".parse::<Timestamp>().unwrap())
+            .end("2022-12-20T15:00:00-0700".parse::<Timestamp>().unwrap())
+            .build()
+            .unwrap();
+        let err = manager.query(query).await.unwrap_err();
+        assert_eq!(err, abi::Error::InvalidTime);
+    }
+
+    async fn query_ids_by_mode(manager: &ReservationManager, mode: abi::QueryMode) -> Vec<String> {
+        let query = abi::ReservationQueryBuilder::default()
+            .resource_id("ocean-view-room-714")
+            .start("2022-12-26T00:00:00Z".parse::<Timestamp>().unwrap())
+            .end("2022-12-27T00:00:00Z".parse::<Timestamp>().unwrap())
+            .mode(mode as i32)
+            .build()
+            .unwrap();
+        manager
+            .query(query)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|r| r.note)
+            .collect()
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn query_modes_should_match_window_differently(migrated_pool: Pool<Postgres>) {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        // window is the day of 2022-12-26, reservations are named by how they relate to it
+        for (note, start, end) in [
+            ("before", "2022-12-25T08:00:00Z", "2022-12-25T18:00:00Z"),
+            (
+                "straddle-start",
+                "2022-12-25T20:00:00Z",
+                "2022-12-26T04:00:00Z",
+            ),
+            ("inside", "2022-12-26T10:00:00Z", "2022-12-26T12:00:00Z"),
+            (
+                "straddle-end",
+                "2022-12-26T20:00:00Z",
+                "2022-12-27T04:00:00Z",
+            ),
+            ("after", "2022-12-27T04:00:00Z", "2022-12-28T00:00:00Z"),
+        ] {
+            let rsvp = abi::Reservation::new_pending(
+                "tyrid",
+                "ocean-view-room-714",
+                start.parse().unwrap(),
+                end.parse().unwrap(),
+                note,
+            );
+            manager.reserve(rsvp).await.unwrap();
+        }
+
+        assert_eq!(
+            query_ids_by_mode(&manager, abi::QueryMode::Contained).await,
+            vec!["inside"]
+        );
+        assert_eq!(
+            query_ids_by_mode(&manager, abi::QueryMode::Overlapping).await,
+            vec!["straddle-start", "inside", "straddle-end"]
+        );
+        assert_eq!(
+            query_ids_by_mode(&manager, abi::QueryMode::StartsWithin).await,
+            vec!["inside", "straddle-end"]
+        );
+        assert_eq!(
+            query_ids_by_mode(&manager, abi::QueryMode::EndsWithin).await,
+            vec!["straddle-start", "inside"]
+        );
+    }
+
+    async fn make_reservations(manager: &ReservationManager, count: i64) -> Vec<abi::Reservation> {
+        let start: DateTime<FixedOffset> = "2023-01-01T10:00:00-0700".parse().unwrap();
+        let mut rsvps = vec![];
+        for i in 0..count {
+            let rsvp = abi::Reservation::new_pending(
+                "tyrid",
+                "ocean-view-room-714",
+                start + Duration::days(i),
+                start + Duration::days(i) + Duration::hours(2),
+                "hello.",
+            );
+            rsvps.push(manager.reserve(rsvp).await.unwrap());
+        }
+        rsvps
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn filter_window_should_keep_reservations_within_it(migrated_pool: Pool<Postgres>) {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        // 10:00-12:00 each day from 2023-01-01, in -0700
+        let rsvps = make_reservations(&manager, 25).await;
+
+        let filter = abi::ReservationFilterBuilder::default()
+            .user_id("tyrid")
+            .start(rsvps[3].start.clone().unwrap())
+            .end(rsvps[17].end.clone().unwrap())
+            .page_size(10)
+            .build()
+            .unwrap();
+        let (pager, page1) = manager.filter(filter.clone()).await.unwrap();
+        assert_eq!(page1, rsvps[3..13]);
+        let (pager, page2) = manager
+            .filter(abi::ReservationFilter {
+                cursor: pager.next,
+                ..filter.clone()
+            })
+            .await
+            .unwrap();
+        assert_eq!(page2, rsvps[13..18]);
+        assert!(pager.next.is_empty());
+
+        // open ended
+        let filter = abi::ReservationFilterBuilder::default()
+            .user_id("tyrid")
+            .start(rsvps[20].start.clone().unwrap())
+            .build()
+            .unwrap();
+        let (_, rows) = manager.filter(filter).await.unwrap();
+        assert_eq!(rows, rsvps[20..25]);
+
+        let filter = abi::ReservationFilterBuilder::default()
+            .start(rsvps[17].end.clone().unwrap())
+            .end(rsvps[3].start.clone().unwrap())
+            .build()
+            .unwrap();
+        assert_eq!(
+            manager.filter(filter).await.unwrap_err(),
+            abi::Error::InvalidTime
+        );
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn filter_should_paginate_with_cursors(migrated_pool: Pool<Postgres>) {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let rsvps = make_reservations(&manager, 25).await;
+
+        let filter = abi::ReservationFilterBuilder::default()
+            .user_id("tyrid")
+            .page_size(10)
+            .build()
+            .unwrap();
+        let (pager, page1) = manager.filter(filter.clone()).await.unwrap();
+        assert_eq!(page1, rsvps[0..10]);
+        assert!(pager.prev.is_empty());
+
+        let (pager, page2) = manager
+            .filter(abi::ReservationFilter {
+                cursor: pager.next,
+                ..filter.clone()
+            })
+            .await
+            .unwrap();
+        assert_eq!(page2, rsvps[10..20]);
+        assert!(!pager.prev.is_empty());
+
+        let (pager, page3) = manager
+            .filter(abi::ReservationFilter {
+                cursor: pager.next,
+                ..filter.clone()
+            })
+            .await
+            .unwrap();
+        assert_eq!(page3, rsvps[20..25]);
+        assert!(pager.next.is_empty());
+
+        // walk back from the last page
+        let (pager, prev) = manager
+            .filter(abi::ReservationFilter {
+                cursor: pager.prev,
+                ..filter.clone()
+            })
+            .await
+            .unwrap();
+        assert_eq!(prev, page2);
+        let (pager, prev) = manager
+            .filter(abi::ReservationFilter {
+                cursor: pager.prev,
+                ..filter.clone()
+            })
+            .await
+            .unwrap();
+        assert_eq!(prev, page1);
+        assert!(pager.prev.is_empty());
+        assert!(!pager.next.is_empty());
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn filter_desc_should_start_from_latest(migrated_pool: Pool<Postgres>) {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let mut rsvps = make_reservations(&manager, 15).await;
+        rsvps.reverse();
+
+        let filter = abi::ReservationFilterBuilder::default()
+            .resource_id("ocean-view-room-714")
+            .status(ReservationStatus::Pending as i32)
+            .desc(true)
+            .build()
+            .unwrap();
+        let (pager, page1) = manager.filter(filter.clone()).await.unwrap();
+        assert_eq!(page1, rsvps[0..10]);
+
+        let (pager, page2) = manager
+            .filter(abi::ReservationFilter {
+                cursor: pager.next,
+                ..filter
+            })
+            .await
+            .unwrap();
+        assert_eq!(page2, rsvps[10..15]);
+        assert!(pager.next.is_empty());
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn filter_with_invalid_cursor_should_reject(migrated_pool: Pool<Postgres>) {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let filter = abi::ReservationFilterBuilder::default()
+            .cursor("bad")
+            .build()
+            .unwrap();
+        let err = manager.filter(filter).await.unwrap_err();
+        assert_eq!(err, abi::Error::InvalidCursor("bad".to_string()));
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn query_stream_should_yield_reservations_in_order(migrated_pool: Pool<Postgres>) {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let rsvp1 = abi::Reservation::new_pending(
+            "tyrid",
+            "ocean-view-room-714",
+            "2022-12-25T15:00:00-0700".parse().unwrap(),
+            "2022-12-26T12:00:00-0700".parse().unwrap(),
+            "hello.",
+        );
+        let rsvp2 = abi::Reservation::new_pending(
+            "tyrid",
+            "ocean-view-room-714",
+            "2022-12-27T15:00:00-0700".parse().unwrap(),
+            "2022-12-28T12:00:00-0700".parse().unwrap(),
+            "hello.",
+        );
+        // insert the later one first, results are ordered by start time
+        let rsvp2 = manager.reserve(rsvp2).await.unwrap();
+        let rsvp1 = manager.reserve(rsvp1).await.unwrap();
+
+        let query = abi::ReservationQueryBuilder::default()
+            .user_id("tyrid")
+            .start("2022-12-20T15:00:00-0700".parse::<Timestamp>().unwrap())
+            .end("2022-12-30T12:00:00-0700".parse::<Timestamp>().unwrap())
+            .status(ReservationStatus::Pending as i32)
+            .build()
+            .unwrap();
+        let mut stream = manager.query_stream(query).await.unwrap();
+
+        assert_eq!(stream.try_next().await.unwrap(), Some(rsvp1));
+        assert_eq!(stream.try_next().await.unwrap(), Some(rsvp2));
+        assert_eq!(stream.try_next().await.unwrap(), None);
+    }
 }
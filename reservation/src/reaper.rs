@@ -0,0 +1,115 @@
+use std::time::Duration;
+
+use sqlx::PgPool;
+use tokio::task::JoinHandle;
+
+use crate::{idempotency, ReservationManager};
+
+// cancelling the rows fires rsvp.reservations_trigger(), so listeners get an update for each
+const EXPIRE_SQL: &str = "UPDATE rsvp.reservations SET status = 'cancelled' WHERE status = 'pending' AND expires_at <= now() RETURNING *";
+
+pub(crate) async fn expire_holds(pool: &PgPool) -> Result<Vec<abi::Reservation>, abi::Error> {
+    let rsvps = sqlx::query_as(EXPIRE_SQL).fetch_all(pool).await?;
+    Ok(rsvps)
+}
+
+impl ReservationManager {
+    /// Spawn a task cancelling expired pending holds and dropping expired idempotency
+    /// keys every `period`. The task runs until the returned handle is aborted.
+    pub fn spawn_reaper(&self, period: Duration) -> JoinHandle<()> {
+        let pool = self.pool.clone();
+        let idempotency_window = self.idempotency_window;
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                interval.tick().await;
+                // a failed round is retried on the next tick
+                if let Err(e) = expire_holds(&pool).await {
+                    tracing::warn!("failed to expire holds: {}", e);
+                }
+                if let Err(e) = idempotency::purge(&pool, idempotency_window).await {
+                    tracing::warn!("failed to purge idempotency keys: {}", e);
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use abi::{ReservationStatus, ReservationUpdateType};
+    use futures::StreamExt;
+    use sqlx::{Pool, Postgres};
+
+    use super::*;
+    use crate::Rsvp;
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn reaper_should_cancel_expired_holds_and_notify(migrated_pool: Pool<Postgres>) {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let mut stream = manager.listen(None).await.unwrap();
+
+        let mut rsvp = abi::Reservation::new_pending(
+            "tyrid",
+            "ocean-view-room-713",
+            "2022-12-25T15:00:00-0700".parse().unwrap(),
+            "2022-12-28T12:00:00-0700".parse().unwrap(),
+            "hello.",
+        );
+        rsvp.hold_ttl = Some(Duration::from_millis(10).try_into().unwrap());
+        let rsvp = manager.reserve(rsvp).await.unwrap();
+
+        let reaper = manager.spawn_reaper(Duration::from_millis(20));
+
+        let mut changes = vec![];
+        for _ in 0..2 {
+            let change = tokio::time::timeout(Duration::from_secs(5), stream.next())
+                .await
+                .unwrap()
+                .unwrap()
+                .unwrap();
+            changes.push(change);
+        }
+        reaper.abort();
+
+        assert_eq!(changes[0].op, ReservationUpdateType::Create as i32);
+        assert_eq!(changes[1].op, ReservationUpdateType::Update as i32);
+        let cancelled = changes[1].reservation.as_ref().unwrap();
+        assert_eq!(cancelled.id, rsvp.id);
+        assert_eq!(cancelled.status, ReservationStatus::Cancelled as i32);
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn reaper_should_purge_expired_idempotency_keys(migrated_pool: Pool<Postgres>) {
+        let manager = ReservationManager::new(migrated_pool.clone())
+            .with_idempotency_window(Duration::from_millis(10));
+        let rsvp = abi::Reservation::new_pending(
+            "tyrid",
+            "ocean-view-room-713",
+            "2022-12-25T15:00:00-0700".parse().unwrap(),
+            "2022-12-28T12:00:00-0700".parse().unwrap(),
+            "hello.",
+        );
+        manager
+            .reserve_idempotent("key-1".into(), rsvp)
+            .await
+            .unwrap();
+
+        let reaper = manager.spawn_reaper(Duration::from_millis(20));
+        let count_keys = || {
+            sqlx::query_scalar::<_, i64>("SELECT count(*) FROM rsvp.idempotency_keys")
+                .fetch_one(&migrated_pool)
+        };
+        let mut keys = count_keys().await.unwrap();
+        for _ in 0..50 {
+            if keys == 0 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            keys = count_keys().await.unwrap();
+        }
+        reaper.abort();
+
+        assert_eq!(keys, 0);
+    }
+}
@@ -0,0 +1,218 @@
+use std::ops::Range;
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use sqlx::PgConnection;
+
+use crate::resource;
+
+// a time in the time zone of the resource
+const LOCAL_TIME_SQL: &str = "SELECT $1::timestamptz AT TIME ZONE $2";
+
+// local start times back to absolute ones, kept in order
+const ABSOLUTE_STARTS_SQL: &str =
+    "SELECT t AT TIME ZONE $2 FROM unnest($1::timestamp[]) WITH ORDINALITY AS o(t, i) ORDER BY i";
+
+// windows of the occurrences of a recurring reservation, the first one being `rsvp`. The
+// rule is expanded in the time zone of the resource, so an occurrence keeps its local time
+// of day across daylight saving changes, and each lasts as long as `rsvp`. An UNTIL in UTC
+// is converted to the time zone of the resource first
+pub(crate) async fn occurrences(
+    conn: &mut PgConnection,
+    rsvp: &abi::Reservation,
+    rule: &abi::RecurrenceRule,
+) -> Result<Vec<Range<DateTime<Utc>>>, abi::Error> {
+    let resource = resource::lock_bookable(&mut *conn, &rsvp.resource_id).await?;
+    let window = rsvp.get_window()?;
+    let timezone = resource.get_timezone();
+
+    let start: NaiveDateTime = sqlx::query_scalar(LOCAL_TIME_SQL)
+        .bind(window.start)
+        .bind(timezone)
+        .fetch_one(&mut *conn)
+        .await?;
+    let mut rule = rule.clone();
+    if let Some(until) = rule.utc_until() {
+        let until = sqlx::query_scalar(LOCAL_TIME_SQL)
+            .bind(until)
+            .bind(timezone)
+            .fetch_one(&mut *conn)
+            .await?;
+        rule = rule.with_local_until(until);
+    }
+    let starts: Vec<DateTime<Utc>> = sqlx::query_scalar(ABSOLUTE_STARTS_SQL)
+        .bind(rule.expand(start)?)
+        .bind(timezone)
+        .fetch_all(conn)
+        .await?;
+
+    let duration = window.end - window.start;
+    Ok(starts
+        .into_iter()
+        .map(|start| start..start + duration)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use abi::{BatchMode, Error, RecurrenceRule, ReservationConflictInfo};
+    use sqlx::{Pool, Postgres};
+
+    use crate::{ReservationManager, Rsvp};
+
+    fn weekly(start: &str, end: &str) -> abi::Reservation {
+        abi::Reservation::new_pending(
+            "tyrid",
+            "ocean-view-room-713",
+            start.parse().unwrap(),
+            end.parse().unwrap(),
+            "weekly sync",
+        )
+    }
+
+    fn rule(rule: &str) -> RecurrenceRule {
+        rule.parse().unwrap()
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn reserve_recurring_should_link_occurrences(migrated_pool: Pool<Postgres>) {
+        let manager = ReservationManager::new(migrated_pool.clone());
+        let rsvp = weekly("2022-12-27T10:00:00+0000", "2022-12-27T11:00:00+0000");
+        let (series_id, occurrences) = manager
+            .reserve_recurring(
+                rsvp,
+                rule("FREQ=WEEKLY;BYDAY=TU;COUNT=12"),
+                BatchMode::Atomic,
+            )
+            .await
+            .unwrap();
+
+        assert!(!series_id.is_empty());
+        assert_eq!(occurrences.len(), 12);
+        let (window, result) = &occurrences[11];
+        assert_eq!(window.start.to_rfc3339(), "2023-03-14T10:00:00+00:00");
+        let last = result.as_ref().unwrap();
+        assert_eq!(last.series_id, series_id);
+        assert_eq!(last.get_window().unwrap(), *window);
+
+        let count: i64 =
+            sqlx::query_scalar("SELECT count(*) FROM rsvp.reservations WHERE series_id = $1::uuid")
+                .bind(&series_id)
+                .fetch_one(&migrated_pool)
+                .await
+                .unwrap();
+        assert_eq!(count, 12);
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn reserve_recurring_should_keep_local_time_across_dst(migrated_pool: Pool<Postgres>) {
+        sqlx::query("UPDATE rsvp.resources SET timezone = 'America/New_York' WHERE id = 'ocean-view-room-713'")
+            .execute(&migrated_pool)
+            .await
+            .unwrap();
+        let manager = ReservationManager::new(migrated_pool);
+        // 10:00 in New York, before and after daylight saving time starts on 2023-03-12
+        let rsvp = weekly("2023-03-07T10:00:00-0500", "2023-03-07T11:00:00-0500");
+        let (_, occurrences) = manager
+            .reserve_recurring(rsvp, rule("FREQ=WEEKLY;COUNT=2"), BatchMode::Atomic)
+            .await
+            .unwrap();
+
+        let starts: Vec<_> = occurrences
+            .iter()
+            .map(|(window, _)| window.start.to_rfc3339())
+            .collect();
+        assert_eq!(
+            starts,
+            vec!["2023-03-07T15:00:00+00:00", "2023-03-14T14:00:00+00:00"]
+        );
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn reserve_recurring_should_take_utc_until_as_utc(migrated_pool: Pool<Postgres>) {
+        sqlx::query("UPDATE rsvp.resources SET timezone = 'America/New_York' WHERE id = 'ocean-view-room-713'")
+            .execute(&migrated_pool)
+            .await
+            .unwrap();
+        let manager = ReservationManager::new(migrated_pool);
+        // 10:00 in New York every day, until 09:00 there on 2023-01-04
+        let rsvp = weekly("2023-01-02T10:00:00-0500", "2023-01-02T11:00:00-0500");
+        let (_, occurrences) = manager
+            .reserve_recurring(
+                rsvp,
+                rule("FREQ=DAILY;UNTIL=20230104T140000Z"),
+                BatchMode::Atomic,
+            )
+            .await
+            .unwrap();
+
+        let starts: Vec<_> = occurrences
+            .iter()
+            .map(|(window, _)| window.start.to_rfc3339())
+            .collect();
+        assert_eq!(
+            starts,
+            vec!["2023-01-02T15:00:00+00:00", "2023-01-03T15:00:00+00:00"]
+        );
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn reserve_recurring_atomic_should_fail_all_on_conflict(migrated_pool: Pool<Postgres>) {
+        let manager = ReservationManager::new(migrated_pool);
+        manager
+            .reserve(weekly(
+                "2023-01-10T10:30:00+0000",
+                "2023-01-10T12:00:00+0000",
+            ))
+            .await
+            .unwrap();
+
+        let rsvp = weekly("2022-12-27T10:00:00+0000", "2022-12-27T11:00:00+0000");
+        let (series_id, occurrences) = manager
+            .reserve_recurring(rsvp, rule("FREQ=WEEKLY;COUNT=4"), BatchMode::Atomic)
+            .await
+            .unwrap();
+
+        assert!(series_id.is_empty());
+        for (i, (_, result)) in occurrences.iter().enumerate() {
+            match (i, result) {
+                (2, Err(Error::ConflictReservation(ReservationConflictInfo::Parsed(_)))) => {}
+                (2, other) => panic!("expected a conflict, got {:?}", other),
+                (_, result) => assert_eq!(result, &Err(Error::BatchAborted)),
+            }
+        }
+
+        // nothing but the existing reservation was stored
+        let query = abi::ReservationQueryBuilder::default().build().unwrap();
+        assert_eq!(manager.query(query).await.unwrap().len(), 1);
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn reserve_recurring_best_effort_should_skip_conflicts(migrated_pool: Pool<Postgres>) {
+        let manager = ReservationManager::new(migrated_pool);
+        manager
+            .reserve(weekly(
+                "2023-01-10T10:30:00+0000",
+                "2023-01-10T12:00:00+0000",
+            ))
+            .await
+            .unwrap();
+
+        let rsvp = weekly("2022-12-27T10:00:00+0000", "2022-12-27T11:00:00+0000");
+        let (series_id, occurrences) = manager
+            .reserve_recurring(rsvp, rule("FREQ=WEEKLY;COUNT=4"), BatchMode::BestEffort)
+            .await
+            .unwrap();
+
+        assert!(!series_id.is_empty());
+        let made: Vec<_> = occurrences
+            .iter()
+            .filter_map(|(_, result)| result.as_ref().ok())
+            .collect();
+        assert_eq!(made.len(), 3);
+        assert!(made.iter().all(|rsvp| rsvp.series_id == series_id));
+        assert!(matches!(
+            occurrences[2].1,
+            Err(Error::ConflictReservation(_))
+        ));
+    }
+}
@@ -0,0 +1,437 @@
+use abi::{ResourceField, Validator};
+use async_trait::async_trait;
+use sqlx::{
+    types::{Json, Uuid},
+    PgConnection, PgExecutor, PgPool,
+};
+
+use crate::{ResourceCatalog, ResourceId, ResourceManager};
+
+// the most active reservations of a resource overlapping at once, buffers included. Ends
+// sort before starts at the same time, the windows being half open
+const MAX_OVERLAP_SQL: &str = "WITH active AS (SELECT effective_timespan AS span FROM rsvp.reservations WHERE resource_id = $1 AND status IN ('pending', 'confirmed')), events AS (SELECT lower(span) AS t, 1 AS d FROM active UNION ALL SELECT upper(span), -1 FROM active) SELECT COALESCE(max(n), 0)::int8 FROM (SELECT sum(d) OVER (ORDER BY t, d) AS n FROM events) s";
+
+#[async_trait]
+impl ResourceCatalog for ResourceManager {
+    async fn create(&self, resource: abi::Resource) -> Result<abi::Resource, abi::Error> {
+        resource.validate()?;
+        check_timezone(&self.pool, resource.get_timezone()).await?;
+
+        let rules = resource.get_rules();
+        let sql = "INSERT INTO rsvp.resources (id, name, kind, timezone, attributes, min_duration, max_duration, min_lead_time, max_horizon, slot_alignment, pre_buffer, post_buffer, capacity) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13) ON CONFLICT (id) DO NOTHING RETURNING *";
+        let created: Option<abi::Resource> = sqlx::query_as(sql)
+            .bind(&resource.id)
+            .bind(&resource.name)
+            .bind(&resource.kind)
+            .bind(resource.get_timezone())
+            .bind(Json(&resource.attributes))
+            .bind(rules.get_min_duration())
+            .bind(rules.get_max_duration())
+            .bind(rules.get_min_lead_time())
+            .bind(rules.get_max_horizon())
+            .bind(rules.get_slot_alignment())
+            .bind(rules.get_pre_buffer())
+            .bind(rules.get_post_buffer())
+            .bind(resource.get_capacity())
+            .fetch_optional(&self.pool)
+            .await?;
+
+        created.ok_or(abi::Error::ResourceAlreadyExists(resource.id))
+    }
+
+    async fn get(&self, id: ResourceId) -> Result<abi::Resource, abi::Error> {
+        let sql = "SELECT * FROM rsvp.resources WHERE id = $1";
+        let resource: Option<abi::Resource> = sqlx::query_as(sql)
+            .bind(&id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        resource.ok_or(abi::Error::ResourceNotFound(id))
+    }
+
+    async fn list(
+        &self,
+        kind: String,
+        include_inactive: bool,
+    ) -> Result<Vec<abi::Resource>, abi::Error> {
+        let sql = "SELECT * FROM rsvp.resources WHERE ($1 = '' OR kind = $1) AND ($2 OR active) ORDER BY id";
+        let resources = sqlx::query_as(sql)
+            .bind(kind)
+            .bind(include_inactive)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(resources)
+    }
+
+    async fn update(
+        &self,
+        request: abi::UpdateResourceRequest,
+    ) -> Result<abi::Resource, abi::Error> {
+        request.validate()?;
+        let fields = request.get_fields()?;
+        let resource = request.resource.unwrap_or_default();
+        if fields.contains(&ResourceField::Timezone) {
+            check_timezone(&self.pool, resource.get_timezone()).await?;
+        }
+
+        let rules = resource.get_rules();
+
+        let mut tx = self.pool.begin().await?;
+        // every updatable field is in the statement, those not in the mask keep their value.
+        // The rules are replaced as a whole
+        let sql = "UPDATE rsvp.resources SET name = CASE WHEN $2 THEN $3 ELSE name END, kind = CASE WHEN $4 THEN $5 ELSE kind END, timezone = CASE WHEN $6 THEN $7 ELSE timezone END, attributes = CASE WHEN $8 THEN $9 ELSE attributes END, active = CASE WHEN $10 THEN $11 ELSE active END, min_duration = CASE WHEN $12 THEN $13 ELSE min_duration END, max_duration = CASE WHEN $12 THEN $14 ELSE max_duration END, min_lead_time = CASE WHEN $12 THEN $15 ELSE min_lead_time END, max_horizon = CASE WHEN $12 THEN $16 ELSE max_horizon END, slot_alignment = CASE WHEN $12 THEN $17 ELSE slot_alignment END, pre_buffer = CASE WHEN $12 THEN $18 ELSE pre_buffer END, post_buffer = CASE WHEN $12 THEN $19 ELSE post_buffer END, capacity = CASE WHEN $20 THEN $21 ELSE capacity END WHERE id = $1 RETURNING *";
+        let updated: Option<abi::Resource> = sqlx::query_as(sql)
+            .bind(&request.id)
+            .bind(fields.contains(&ResourceField::Name))
+            .bind(&resource.name)
+            .bind(fields.contains(&ResourceField::Kind))
+            .bind(&resource.kind)
+            .bind(fields.contains(&ResourceField::Timezone))
+            .bind(resource.get_timezone())
+            .bind(fields.contains(&ResourceField::Attributes))
+            .bind(Json(&resource.attributes))
+            .bind(fields.contains(&ResourceField::Active))
+            .bind(resource.active)
+            .bind(fields.contains(&ResourceField::Rules))
+            .bind(rules.get_min_duration())
+            .bind(rules.get_max_duration())
+            .bind(rules.get_min_lead_time())
+            .bind(rules.get_max_horizon())
+            .bind(rules.get_slot_alignment())
+            .bind(rules.get_pre_buffer())
+            .bind(rules.get_post_buffer())
+            .bind(fields.contains(&ResourceField::Capacity))
+            .bind(resource.get_capacity())
+            .fetch_optional(&mut *tx)
+            .await?;
+        let updated = updated.ok_or(abi::Error::ResourceNotFound(request.id))?;
+
+        // only reservations of resources with a capacity of 1 are kept apart by the exclusion
+        // constraint, which rejects the change if they already overlap. Above 1, the
+        // reservations are counted the way they are when made
+        if fields.contains(&ResourceField::Capacity) {
+            let capacity = updated.get_capacity();
+            let sql = "UPDATE rsvp.reservations SET exclusive = $2 WHERE resource_id = $1 AND exclusive <> $2";
+            sqlx::query(sql)
+                .bind(&updated.id)
+                .bind(capacity == 1)
+                .execute(&mut *tx)
+                .await?;
+
+            if capacity > 1 {
+                lock_reservations(&mut tx, &updated.id).await?;
+                let overlapping: i64 = sqlx::query_scalar(MAX_OVERLAP_SQL)
+                    .bind(&updated.id)
+                    .fetch_one(&mut *tx)
+                    .await?;
+                if overlapping > capacity as i64 {
+                    return Err(abi::Error::CapacityTooLow {
+                        capacity,
+                        overlapping,
+                    });
+                }
+            }
+        }
+        tx.commit().await?;
+
+        Ok(updated)
+    }
+
+    async fn deactivate(&self, id: ResourceId) -> Result<abi::Resource, abi::Error> {
+        let sql = "UPDATE rsvp.resources SET active = FALSE WHERE id = $1 RETURNING *";
+        let resource: Option<abi::Resource> = sqlx::query_as(sql)
+            .bind(&id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        resource.ok_or(abi::Error::ResourceNotFound(id))
+    }
+}
+
+impl ResourceManager {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+// get a resource to reserve within the transaction of `conn`. The row is share locked,
+// so the resource can't be deactivated until the transaction ends
+pub(crate) async fn lock_bookable(
+    conn: &mut PgConnection,
+    id: &str,
+) -> Result<abi::Resource, abi::Error> {
+    let sql = "SELECT * FROM rsvp.resources WHERE id = $1 FOR SHARE";
+    let resource: Option<abi::Resource> = sqlx::query_as(sql).bind(id).fetch_optional(conn).await?;
+
+    match resource {
+        Some(resource) if resource.active => Ok(resource),
+        Some(_) => Err(abi::Error::ResourceInactive(id.to_string())),
+        None => Err(abi::Error::ResourceNotFound(id.to_string())),
+    }
+}
+
+// serialize the reservations made on a resource until the transaction of `conn` ends,
+// for checks the exclusion constraint can't do
+pub(crate) async fn lock_reservations(conn: &mut PgConnection, id: &str) -> Result<(), abi::Error> {
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1))")
+        .bind(id)
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+// get the resource of a reservation within the transaction of `conn`, share locked like
+// `lock_bookable` but whether it is active or not
+pub(crate) async fn lock_reserved(
+    conn: &mut PgConnection,
+    reservation_id: Uuid,
+) -> Result<abi::Resource, abi::Error> {
+    let sql = "SELECT r.* FROM rsvp.resources r JOIN rsvp.reservations v ON v.resource_id = r.id WHERE v.id = $1 FOR SHARE OF r";
+    let resource: Option<abi::Resource> = sqlx::query_as(sql)
+        .bind(reservation_id)
+        .fetch_optional(conn)
+        .await?;
+
+    resource.ok_or(abi::Error::NotFound)
+}
+
+async fn check_timezone(executor: impl PgExecutor<'_>, timezone: &str) -> Result<(), abi::Error> {
+    let sql = "SELECT EXISTS (SELECT 1 FROM pg_timezone_names WHERE name = $1)";
+    let known: bool = sqlx::query_scalar(sql)
+        .bind(timezone)
+        .fetch_one(executor)
+        .await?;
+    if !known {
+        return Err(abi::Error::InvalidTimezone(timezone.to_string()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use prost_types::FieldMask;
+    use sqlx::{Pool, Postgres};
+
+    use super::*;
+
+    fn new_resource(id: &str, kind: &str) -> abi::Resource {
+        abi::Resource {
+            id: id.to_string(),
+            name: format!("Resource {}", id),
+            kind: kind.to_string(),
+            timezone: "America/Los_Angeles".to_string(),
+            attributes: [("floor".to_string(), "7".to_string())].into(),
+            active: false,
+            rules: None,
+            capacity: 0,
+        }
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn create_resource_should_work(migrated_pool: Pool<Postgres>) {
+        let manager = ResourceManager::new(migrated_pool.clone());
+        let resource = manager
+            .create(new_resource("desk-1", "desk"))
+            .await
+            .unwrap();
+        assert!(resource.active);
+        assert_eq!(resource.attributes["floor"], "7");
+        assert_eq!(manager.get("desk-1".into()).await.unwrap(), resource);
+
+        let err = manager
+            .create(new_resource("desk-1", "desk"))
+            .await
+            .unwrap_err();
+        assert_eq!(err, abi::Error::ResourceAlreadyExists("desk-1".into()));
+
+        let err = manager.get("desk-2".into()).await.unwrap_err();
+        assert_eq!(err, abi::Error::ResourceNotFound("desk-2".into()));
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn create_resource_with_unknown_timezone_should_reject(migrated_pool: Pool<Postgres>) {
+        let manager = ResourceManager::new(migrated_pool.clone());
+        let mut resource = new_resource("desk-1", "desk");
+        resource.timezone = "Mars/Olympus_Mons".to_string();
+
+        let err = manager.create(resource).await.unwrap_err();
+        assert_eq!(
+            err,
+            abi::Error::InvalidTimezone("Mars/Olympus_Mons".to_string())
+        );
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn list_resources_should_filter_kind_and_inactive(migrated_pool: Pool<Postgres>) {
+        let manager = ResourceManager::new(migrated_pool.clone());
+        manager
+            .create(new_resource("desk-1", "desk"))
+            .await
+            .unwrap();
+        manager
+            .create(new_resource("desk-2", "desk"))
+            .await
+            .unwrap();
+        manager.deactivate("desk-2".into()).await.unwrap();
+
+        let ids = |resources: Vec<abi::Resource>| -> Vec<String> {
+            resources.into_iter().map(|r| r.id).collect()
+        };
+        let desks = manager.list("desk".into(), false).await.unwrap();
+        assert_eq!(ids(desks), vec!["desk-1"]);
+        let desks = manager.list("desk".into(), true).await.unwrap();
+        assert_eq!(ids(desks), vec!["desk-1", "desk-2"]);
+        let all = manager.list("".into(), false).await.unwrap();
+        assert_eq!(all.len(), 5);
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn update_resource_should_only_change_masked_fields(migrated_pool: Pool<Postgres>) {
+        let manager = ResourceManager::new(migrated_pool.clone());
+        let request = abi::UpdateResourceRequest {
+            id: "ocean-view-room-713".to_string(),
+            field_mask: Some(FieldMask {
+                paths: vec!["name".to_string(), "attributes".to_string()],
+            }),
+            resource: Some(new_resource("ignored", "ignored")),
+        };
+
+        let resource = manager.update(request).await.unwrap();
+        assert_eq!(resource.id, "ocean-view-room-713");
+        assert_eq!(resource.name, "Resource ignored");
+        assert_eq!(resource.kind, "room");
+        assert_eq!(resource.timezone, "UTC");
+        assert_eq!(resource.attributes["floor"], "7");
+        assert!(resource.active);
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn update_resource_rules_should_replace_all_rules(migrated_pool: Pool<Postgres>) {
+        let manager = ResourceManager::new(migrated_pool.clone());
+        let minutes = |m: i64| {
+            Some(prost_types::Duration {
+                seconds: m * 60,
+                nanos: 0,
+            })
+        };
+        let mut request = abi::UpdateResourceRequest {
+            id: "ocean-view-room-713".to_string(),
+            field_mask: Some(FieldMask {
+                paths: vec!["rules".to_string()],
+            }),
+            resource: Some(abi::Resource {
+                rules: Some(abi::BookingRules {
+                    min_duration: minutes(30),
+                    slot_alignment: minutes(15),
+                    post_buffer: minutes(10),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+        };
+        let resource = manager.update(request.clone()).await.unwrap();
+        let rules = resource.rules.unwrap();
+        assert_eq!(rules.min_duration, minutes(30));
+        assert_eq!(rules.slot_alignment, minutes(15));
+        assert_eq!(rules.post_buffer, minutes(10));
+        assert_eq!(rules.max_duration, None);
+
+        request.resource = Some(abi::Resource::default());
+        let resource = manager.update(request).await.unwrap();
+        assert_eq!(resource.rules, None);
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn lower_capacity_should_reject_overlapping_reservations(migrated_pool: Pool<Postgres>) {
+        let manager = ResourceManager::new(migrated_pool.clone());
+        let capacity = |capacity: i32| abi::UpdateResourceRequest {
+            id: "ocean-view-room-713".to_string(),
+            field_mask: Some(FieldMask {
+                paths: vec!["capacity".to_string()],
+            }),
+            resource: Some(abi::Resource {
+                capacity,
+                ..Default::default()
+            }),
+        };
+        let resource = manager.update(capacity(2)).await.unwrap();
+        assert_eq!(resource.capacity, 2);
+
+        let sql = "INSERT INTO rsvp.reservations (user_id, resource_id, timespan, exclusive) VALUES ('tyrid', 'ocean-view-room-713', '[2023-01-02 10:00+00, 2023-01-02 12:00+00)', FALSE)";
+        for _ in 0..2 {
+            sqlx::query(sql).execute(&migrated_pool).await.unwrap();
+        }
+        let err = manager.update(capacity(1)).await.unwrap_err();
+        assert!(matches!(err, abi::Error::ConflictReservation(_)));
+        assert_eq!(
+            manager
+                .get("ocean-view-room-713".into())
+                .await
+                .unwrap()
+                .capacity,
+            2
+        );
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn lower_capacity_should_count_overlapping_reservations(migrated_pool: Pool<Postgres>) {
+        let manager = ResourceManager::new(migrated_pool.clone());
+        let capacity = |capacity: i32| abi::UpdateResourceRequest {
+            id: "ocean-view-room-713".to_string(),
+            field_mask: Some(FieldMask {
+                paths: vec!["capacity".to_string()],
+            }),
+            resource: Some(abi::Resource {
+                capacity,
+                ..Default::default()
+            }),
+        };
+        manager.update(capacity(3)).await.unwrap();
+
+        // three at once between 11:00 and 11:30, the last one only touching the first
+        let sql = "INSERT INTO rsvp.reservations (user_id, resource_id, timespan, exclusive) VALUES ('tyrid', 'ocean-view-room-713', $1::tstzrange, FALSE)";
+        for span in [
+            "[2023-01-02 10:00+00, 2023-01-02 12:00+00)",
+            "[2023-01-02 11:00+00, 2023-01-02 13:00+00)",
+            "[2023-01-02 10:30+00, 2023-01-02 11:30+00)",
+            "[2023-01-02 12:00+00, 2023-01-02 12:30+00)",
+        ] {
+            sqlx::query(sql)
+                .bind(span)
+                .execute(&migrated_pool)
+                .await
+                .unwrap();
+        }
+
+        let err = manager.update(capacity(2)).await.unwrap_err();
+        assert_eq!(
+            err,
+            abi::Error::CapacityTooLow {
+                capacity: 2,
+                overlapping: 3
+            }
+        );
+        assert_eq!(
+            manager
+                .get("ocean-view-room-713".into())
+                .await
+                .unwrap()
+                .capacity,
+            3
+        );
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn deactivate_resource_should_keep_it_listed_as_inactive(migrated_pool: Pool<Postgres>) {
+        let manager = ResourceManager::new(migrated_pool.clone());
+        let resource = manager
+            .deactivate("ocean-view-room-713".into())
+            .await
+            .unwrap();
+        assert!(!resource.active);
+
+        let err = manager.deactivate("desk-1".into()).await.unwrap_err();
+        assert_eq!(err, abi::Error::ResourceNotFound("desk-1".into()));
+    }
+}
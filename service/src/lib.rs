@@ -0,0 +1,53 @@
+mod service;
+
+use std::{net::SocketAddr, pin::Pin, time::Duration};
+
+use abi::reservation_service_server::ReservationServiceServer;
+use futures::Stream;
+use reservation::{ReservationManager, ResourceManager};
+use sqlx::PgPool;
+use tonic::{transport::Server, Status};
+
+// how often expired pending holds are cancelled
+const HOLD_REAPER_PERIOD: Duration = Duration::from_secs(1);
+
+pub type QueryResponseStream =
+    Pin<Box<dyn Stream<Item = Result<abi::QueryResponse, Status>> + Send>>;
+pub type ListenResponseStream =
+    Pin<Box<dyn Stream<Item = Result<abi::ListenResponse, Status>> + Send>>;
+
+pub struct RsvpService {
+    manager: ReservationManager,
+    resources: ResourceManager,
+}
+
+impl RsvpService {
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            manager: ReservationManager::new(pool.clone()),
+            resources: ResourceManager::new(pool),
+        }
+    }
+
+    pub async fn from_url(db_url: &str) -> Result<Self, sqlx::Error> {
+        let pool = PgPool::connect(db_url).await?;
+        Ok(Self::new(pool))
+    }
+}
+
+pub async fn start_server(
+    addr: SocketAddr,
+    db_url: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let svc = RsvpService::from_url(db_url).await?;
+    svc.manager.spawn_reaper(HOLD_REAPER_PERIOD);
+
+    tracing::info!("listening on {}", addr);
+
+    Server::builder()
+        .add_service(ReservationServiceServer::new(svc))
+        .serve(addr)
+        .await?;
+
+    Ok(())
+}
@@ -1,3 +1,15 @@
-fn main() {
-    println!("Hello, world!");
+use std::{env, net::SocketAddr};
+
+const DEFAULT_ADDR: &str = "0.0.0.0:50051";
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    tracing_subscriber::fmt::init();
+
+    let db_url = env::var("DATABASE_URL")?;
+    let addr: SocketAddr = env::var("RESERVATION_ADDR")
+        .unwrap_or_else(|_| DEFAULT_ADDR.to_string())
+        .parse()?;
+
+    reservation_service::start_server(addr, &db_url).await
 }
@@ -0,0 +1,833 @@
+use abi::query_response::Data;
+use abi::{
+    reservation_service_server::ReservationService, CancelRequest, CancelResponse, ConfirmRequest,
+    ConfirmResponse, CreateResourceRequest, CreateResourceResponse, DeactivateResourceRequest,
+    DeactivateResourceResponse, FilterRequest, FilterResponse, FindAvailabilityRequest,
+    FindAvailabilityResponse, GetRequest, GetResourceRequest, GetResourceResponse, GetResponse,
+    ListResourcesRequest, ListResourcesResponse, ListenRequest, QueryRequest, QueryResponse,
+    ReserveBatchRequest, ReserveBatchResponse, ReserveRecurringRequest, ReserveRecurringResponse,
+    ReserveRequest, ReserveResponse, TimeSlot, UpdateRequest, UpdateResourceRequest,
+    UpdateResourceResponse, UpdateResponse,
+};
+use futures::{stream, StreamExt, TryStreamExt};
+use reservation::{ResourceCatalog, Rsvp};
+use tonic::{Request, Response, Status};
+
+use crate::{ListenResponseStream, QueryResponseStream, RsvpService};
+
+#[tonic::async_trait]
+impl ReservationService for RsvpService {
+    async fn reserve(
+        &self,
+        request: Request<ReserveRequest>,
+    ) -> Result<Response<ReserveResponse>, Status> {
+        let request = request.into_inner();
+        let Some(reservation) = request.reservation else {
+            return Err(Status::invalid_argument("missing reservation"));
+        };
+        let reservation = self
+            .manager
+            .reserve_idempotent(request.idempotency_key, reservation)
+            .await?;
+
+        Ok(Response::new(ReserveResponse {
+            reservation: Some(reservation),
+        }))
+    }
+
+    async fn reserve_batch(
+        &self,
+        request: Request<ReserveBatchRequest>,
+    ) -> Result<Response<ReserveBatchResponse>, Status> {
+        let request = request.into_inner();
+        let mode = abi::BatchMode::try_from(request.mode).unwrap_or(abi::BatchMode::Atomic);
+        let results = self
+            .manager
+            .reserve_batch(request.reservations, mode)
+            .await?;
+
+        Ok(Response::new(ReserveBatchResponse {
+            results: results.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    async fn reserve_recurring(
+        &self,
+        request: Request<ReserveRecurringRequest>,
+    ) -> Result<Response<ReserveRecurringResponse>, Status> {
+        let request = request.into_inner();
+        let Some(recurring) = request.recurring else {
+            return Err(Status::invalid_argument("missing recurring reservation"));
+        };
+        let Some(reservation) = recurring.reservation else {
+            return Err(Status::invalid_argument("missing reservation"));
+        };
+        let rule = recurring.rrule.parse()?;
+        let mode = abi::BatchMode::try_from(request.mode).unwrap_or(abi::BatchMode::Atomic);
+        let (series_id, occurrences) = self
+            .manager
+            .reserve_recurring(reservation, rule, mode)
+            .await?;
+
+        let (windows, results): (Vec<_>, Vec<_>) = occurrences.into_iter().unzip();
+        Ok(Response::new(ReserveRecurringResponse {
+            series_id,
+            occurrences: windows
+                .into_iter()
+                .map(|window| TimeSlot::new(window.start, window.end))
+                .collect(),
+            results: results.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    async fn confirm(
+        &self,
+        request: Request<ConfirmRequest>,
+    ) -> Result<Response<ConfirmResponse>, Status> {
+        let request = request.into_inner();
+        let Some(reservation) = request.reservation else {
+            return Err(Status::invalid_argument("missing reservation"));
+        };
+        let reservation = self
+            .manager
+            .change_status_idempotent(
+                request.idempotency_key,
+                reservation.id,
+                request.expected_version,
+            )
+            .await?;
+
+        Ok(Response::new(ConfirmResponse {
+            reservation: Some(reservation),
+        }))
+    }
+
+    async fn update(
+        &self,
+        request: Request<UpdateRequest>,
+    ) -> Result<Response<UpdateResponse>, Status> {
+        let request = request.into_inner();
+        let reservation = self.manager.update(request).await?;
+
+        Ok(Response::new(UpdateResponse {
+            reservation: Some(reservation),
+        }))
+    }
+
+    async fn cancel(
+        &self,
+        request: Request<CancelRequest>,
+    ) -> Result<Response<CancelResponse>, Status> {
+        let request = request.into_inner();
+        let reservation = self
+            .manager
+            .cancel(request.id, request.expected_version)
+            .await?;
+
+        Ok(Response::new(CancelResponse {
+            reservation: Some(reservation),
+        }))
+    }
+
+    async fn get(&self, request: Request<GetRequest>) -> Result<Response<GetResponse>, Status> {
+        let request = request.into_inner();
+        let reservation = self.manager.get(request.id).await?;
+
+        Ok(Response::new(GetResponse {
+            reservation: Some(reservation),
+        }))
+    }
+
+    type queryStream = QueryResponseStream;
+
+    async fn query(
+        &self,
+        request: Request<QueryRequest>,
+    ) -> Result<Response<Self::queryStream>, Status> {
+        let request = request.into_inner();
+        let Some(query) = request.query else {
+            return Err(Status::invalid_argument("missing query"));
+        };
+        let (pager, rows) = self.manager.query_page(query).await?;
+
+        // the pager goes first so clients know the total before the rows arrive
+        let pager = QueryResponse {
+            data: Some(Data::Pager(pager)),
+        };
+        let rows = rows.map_ok(|rsvp| QueryResponse {
+            data: Some(Data::Reservation(rsvp)),
+        });
+        let stream = stream::once(async { Ok(pager) })
+            .chain(rows)
+            .map_err(Status::from);
+
+        Ok(Response::new(Box::pin(stream)))
+    }
+
+    async fn find_availability(
+        &self,
+        request: Request<FindAvailabilityRequest>,
+    ) -> Result<Response<FindAvailabilityResponse>, Status> {
+        let request = request.into_inner();
+        let window = request.get_window()?;
+        let slots = self
+            .manager
+            .availability(
+                request.resource_id.clone(),
+                window,
+                request.get_min_duration(),
+                request.get_granularity(),
+            )
+            .await?;
+
+        Ok(Response::new(FindAvailabilityResponse { slots }))
+    }
+
+    async fn filter(
+        &self,
+        request: Request<FilterRequest>,
+    ) -> Result<Response<FilterResponse>, Status> {
+        let request = request.into_inner();
+        let Some(filter) = request.filter else {
+            return Err(Status::invalid_argument("missing filter"));
+        };
+        let (pager, reservations) = self.manager.filter(filter).await?;
+
+        Ok(Response::new(FilterResponse {
+            reservations,
+            pager: Some(pager),
+        }))
+    }
+
+    type listenStream = ListenResponseStream;
+
+    async fn listen(
+        &self,
+        request: Request<ListenRequest>,
+    ) -> Result<Response<Self::listenStream>, Status> {
+        let request = request.into_inner();
+        let stream = self.manager.listen(request.since_change_id).await?;
+        let stream = stream.map_err(Status::from);
+
+        Ok(Response::new(Box::pin(stream)))
+    }
+
+    async fn create_resource(
+        &self,
+        request: Request<CreateResourceRequest>,
+    ) -> Result<Response<CreateResourceResponse>, Status> {
+        let request = request.into_inner();
+        let Some(resource) = request.resource else {
+            return Err(Status::invalid_argument("missing resource"));
+        };
+        let resource = self.resources.create(resource).await?;
+
+        Ok(Response::new(CreateResourceResponse {
+            resource: Some(resource),
+        }))
+    }
+
+    async fn get_resource(
+        &self,
+        request: Request<GetResourceRequest>,
+    ) -> Result<Response<GetResourceResponse>, Status> {
+        let request = request.into_inner();
+        let resource = self.resources.get(request.id).await?;
+
+        Ok(Response::new(GetResourceResponse {
+            resource: Some(resource),
+        }))
+    }
+
+    async fn list_resources(
+        &self,
+        request: Request<ListResourcesRequest>,
+    ) -> Result<Response<ListResourcesResponse>, Status> {
+        let request = request.into_inner();
+        let resources = self
+            .resources
+            .list(request.kind, request.include_inactive)
+            .await?;
+
+        Ok(Response::new(ListResourcesResponse { resources }))
+    }
+
+    async fn update_resource(
+        &self,
+        request: Request<UpdateResourceRequest>,
+    ) -> Result<Response<UpdateResourceResponse>, Status> {
+        let request = request.into_inner();
+        let resource = self.resources.update(request).await?;
+
+        Ok(Response::new(UpdateResourceResponse {
+            resource: Some(resource),
+        }))
+    }
+
+    async fn deactivate_resource(
+        &self,
+        request: Request<DeactivateResourceRequest>,
+    ) -> Result<Response<DeactivateResourceResponse>, Status> {
+        let request = request.into_inner();
+        let resource = self.resources.deactivate(request.id).await?;
+
+        Ok(Response::new(DeactivateResourceResponse {
+            resource: Some(resource),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use abi::{
+        reserve_batch_result::Result as BatchResult, ConflictDetails, FilterPager,
+        ReservationFilterBuilder, ReservationQueryBuilder, ReservationStatus,
+        ReservationUpdateType,
+    };
+    use futures::StreamExt;
+    use prost::Message;
+    use prost_types::{FieldMask, Timestamp};
+    use sqlx::{Pool, Postgres};
+
+    use super::*;
+
+    fn new_rsvp(rid: &str) -> abi::Reservation {
+        abi::Reservation::new_pending(
+            "tyrid",
+            rid,
+            "2022-12-25T15:00:00-0700".parse().unwrap(),
+            "2022-12-28T12:00:00-0700".parse().unwrap(),
+            "hello.",
+        )
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn rpc_reserve_and_get_should_work(pool: Pool<Postgres>) {
+        let service = RsvpService::new(pool);
+        let rsvp = service
+            .reserve(Request::new(ReserveRequest {
+                reservation: Some(new_rsvp("ocean-view-room-713")),
+                ..Default::default()
+            }))
+            .await
+            .unwrap()
+            .into_inner()
+            .reservation
+            .unwrap();
+        assert!(!rsvp.id.is_empty());
+
+        let got = service
+            .get(Request::new(GetRequest {
+                id: rsvp.id.clone(),
+            }))
+            .await
+            .unwrap()
+            .into_inner()
+            .reservation
+            .unwrap();
+        assert_eq!(got, rsvp);
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn rpc_confirm_should_work(pool: Pool<Postgres>) {
+        let service = RsvpService::new(pool);
+        let rsvp = service
+            .reserve(Request::new(ReserveRequest {
+                reservation: Some(new_rsvp("ocean-view-room-713")),
+                ..Default::default()
+            }))
+            .await
+            .unwrap()
+            .into_inner()
+            .reservation;
+
+        let rsvp = service
+            .confirm(Request::new(ConfirmRequest {
+                reservation: rsvp,
+                ..Default::default()
+            }))
+            .await
+            .unwrap()
+            .into_inner()
+            .reservation
+            .unwrap();
+        assert_eq!(rsvp.status, ReservationStatus::Confirmed as i32);
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn rpc_retried_reserve_should_return_original(pool: Pool<Postgres>) {
+        let service = RsvpService::new(pool);
+        let request = ReserveRequest {
+            reservation: Some(new_rsvp("ocean-view-room-713")),
+            idempotency_key: "checkout-42".into(),
+        };
+        let first = service
+            .reserve(Request::new(request.clone()))
+            .await
+            .unwrap()
+            .into_inner();
+        let retried = service
+            .reserve(Request::new(request))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(retried, first);
+
+        let confirm = ConfirmRequest {
+            reservation: first.reservation,
+            idempotency_key: "checkout-42".into(),
+            ..Default::default()
+        };
+        let first = service
+            .confirm(Request::new(confirm.clone()))
+            .await
+            .unwrap()
+            .into_inner();
+        let retried = service
+            .confirm(Request::new(confirm))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(retried, first);
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn rpc_cancel_should_return_cancelled_reservation(pool: Pool<Postgres>) {
+        let service = RsvpService::new(pool);
+        let rsvp = service
+            .reserve(Request::new(ReserveRequest {
+                reservation: Some(new_rsvp("ocean-view-room-713")),
+                ..Default::default()
+            }))
+            .await
+            .unwrap()
+            .into_inner()
+            .reservation
+            .unwrap();
+
+        let cancelled = service
+            .cancel(Request::new(CancelRequest {
+                id: rsvp.id.clone(),
+                ..Default::default()
+            }))
+            .await
+            .unwrap()
+            .into_inner()
+            .reservation
+            .unwrap();
+        assert_eq!(cancelled.id, rsvp.id);
+        assert_eq!(cancelled.status, ReservationStatus::Cancelled as i32);
+
+        let got = service
+            .get(Request::new(GetRequest { id: rsvp.id }))
+            .await
+            .unwrap()
+            .into_inner()
+            .reservation
+            .unwrap();
+        assert_eq!(got, cancelled);
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn rpc_reserve_conflict_should_return_details(pool: Pool<Postgres>) {
+        let service = RsvpService::new(pool);
+        service
+            .reserve(Request::new(ReserveRequest {
+                reservation: Some(new_rsvp("ocean-view-room-713")),
+                ..Default::default()
+            }))
+            .await
+            .unwrap();
+
+        let err = service
+            .reserve(Request::new(ReserveRequest {
+                reservation: Some(new_rsvp("ocean-view-room-713")),
+                ..Default::default()
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::AlreadyExists);
+
+        let details = ConflictDetails::decode(err.details()).unwrap();
+        assert_eq!(details.old.unwrap().resource_id, "ocean-view-room-713");
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn rpc_update_should_apply_field_mask(pool: Pool<Postgres>) {
+        let service = RsvpService::new(pool);
+        let rsvp = service
+            .reserve(Request::new(ReserveRequest {
+                reservation: Some(new_rsvp("ocean-view-room-713")),
+                ..Default::default()
+            }))
+            .await
+            .unwrap()
+            .into_inner()
+            .reservation
+            .unwrap();
+
+        let changes = abi::Reservation {
+            note: "late check-in.".into(),
+            ..Default::default()
+        };
+        let updated = service
+            .update(Request::new(UpdateRequest {
+                id: rsvp.id.clone(),
+                field_mask: Some(FieldMask {
+                    paths: vec!["note".into()],
+                }),
+                reservation: Some(changes.clone()),
+                ..Default::default()
+            }))
+            .await
+            .unwrap()
+            .into_inner()
+            .reservation
+            .unwrap();
+        assert_eq!(updated.note, "late check-in.");
+        assert_eq!(updated.user_id, rsvp.user_id);
+
+        let err = service
+            .update(Request::new(UpdateRequest {
+                id: rsvp.id,
+                field_mask: Some(FieldMask {
+                    paths: vec!["status".into()],
+                }),
+                reservation: Some(changes),
+                ..Default::default()
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn rpc_reserve_batch_should_report_each_result(pool: Pool<Postgres>) {
+        let service = RsvpService::new(pool);
+        let request = ReserveBatchRequest {
+            reservations: vec![
+                new_rsvp("ocean-view-room-713"),
+                new_rsvp("ocean-view-room-714"),
+                new_rsvp("ocean-view-room-713"),
+            ],
+            mode: abi::BatchMode::BestEffort as i32,
+        };
+        let results = service
+            .reserve_batch(Request::new(request))
+            .await
+            .unwrap()
+            .into_inner()
+            .results;
+
+        assert_eq!(results.len(), 3);
+        assert!(matches!(
+            results[0].result,
+            Some(BatchResult::Reservation(_))
+        ));
+        assert!(matches!(
+            results[1].result,
+            Some(BatchResult::Reservation(_))
+        ));
+        let Some(BatchResult::Conflict(conflict)) = &results[2].result else {
+            panic!("expected conflict, got {:?}", results[2]);
+        };
+        assert_eq!(
+            conflict.old.as_ref().unwrap().resource_id,
+            "ocean-view-room-713"
+        );
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn rpc_reserve_recurring_should_skip_conflicts(pool: Pool<Postgres>) {
+        let service = RsvpService::new(pool);
+        let first = service
+            .reserve(Request::new(ReserveRequest {
+                reservation: Some(new_rsvp("ocean-view-room-713")),
+                ..Default::default()
+            }))
+            .await
+            .unwrap()
+            .into_inner()
+            .reservation
+            .unwrap();
+
+        let request = ReserveRecurringRequest {
+            recurring: Some(abi::RecurringReservation {
+                reservation: Some(new_rsvp("ocean-view-room-713")),
+                rrule: "FREQ=DAILY;INTERVAL=7;COUNT=3".to_string(),
+            }),
+            mode: abi::BatchMode::BestEffort as i32,
+        };
+        let response = service
+            .reserve_recurring(Request::new(request))
+            .await
+            .unwrap()
+            .into_inner();
+
+        assert!(!response.series_id.is_empty());
+        assert_eq!(response.occurrences.len(), 3);
+        assert_eq!(response.occurrences[0].start, first.start);
+        assert!(matches!(
+            response.results[0].result,
+            Some(BatchResult::Conflict(_))
+        ));
+        let Some(BatchResult::Reservation(rsvp)) = &response.results[1].result else {
+            panic!("expected reservation, got {:?}", response.results[1]);
+        };
+        assert_eq!(rsvp.series_id, response.series_id);
+
+        let err = service
+            .reserve_recurring(Request::new(ReserveRecurringRequest {
+                recurring: Some(abi::RecurringReservation {
+                    reservation: Some(new_rsvp("ocean-view-room-713")),
+                    rrule: "FREQ=WEEKLY".to_string(),
+                }),
+                ..Default::default()
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn rpc_find_availability_should_return_slots(pool: Pool<Postgres>) {
+        let service = RsvpService::new(pool);
+        service
+            .reserve(Request::new(ReserveRequest {
+                reservation: Some(new_rsvp("ocean-view-room-713")),
+                ..Default::default()
+            }))
+            .await
+            .unwrap();
+
+        let request = FindAvailabilityRequest {
+            resource_id: "ocean-view-room-713".into(),
+            start: Some("2022-12-25T00:00:00Z".parse().unwrap()),
+            end: Some("2022-12-30T00:00:00Z".parse().unwrap()),
+            min_duration: Some(prost_types::Duration {
+                seconds: 86400,
+                nanos: 0,
+            }),
+            ..Default::default()
+        };
+        let slots = service
+            .find_availability(Request::new(request.clone()))
+            .await
+            .unwrap()
+            .into_inner()
+            .slots;
+        // the reservation takes 2022-12-25T22:00Z to 2022-12-28T19:00Z
+        assert_eq!(slots.len(), 1);
+        assert_eq!(
+            slots[0].start,
+            Some("2022-12-28T19:00:00Z".parse().unwrap())
+        );
+
+        let err = service
+            .find_availability(Request::new(FindAvailabilityRequest {
+                end: None,
+                ..request
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn rpc_listen_should_stream_changes(pool: Pool<Postgres>) {
+        let service = RsvpService::new(pool);
+        let mut stream = service
+            .listen(Request::new(ListenRequest::default()))
+            .await
+            .unwrap()
+            .into_inner();
+
+        let rsvp = service
+            .reserve(Request::new(ReserveRequest {
+                reservation: Some(new_rsvp("ocean-view-room-713")),
+                ..Default::default()
+            }))
+            .await
+            .unwrap()
+            .into_inner()
+            .reservation
+            .unwrap();
+
+        let change = tokio::time::timeout(Duration::from_secs(5), stream.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert_eq!(change.op, ReservationUpdateType::Create as i32);
+        assert_eq!(change.reservation.unwrap(), rsvp);
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn rpc_filter_should_return_pager(pool: Pool<Postgres>) {
+        let service = RsvpService::new(pool);
+        service
+            .reserve(Request::new(ReserveRequest {
+                reservation: Some(new_rsvp("ocean-view-room-713")),
+                ..Default::default()
+            }))
+            .await
+            .unwrap();
+
+        let filter = ReservationFilterBuilder::default()
+            .user_id("tyrid")
+            .build()
+            .unwrap();
+        let response = service
+            .filter(Request::new(FilterRequest {
+                filter: Some(filter),
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(response.reservations.len(), 1);
+        assert_eq!(response.pager, Some(FilterPager::default()));
+
+        let filter = ReservationFilterBuilder::default()
+            .cursor("bad")
+            .build()
+            .unwrap();
+        let err = service
+            .filter(Request::new(FilterRequest {
+                filter: Some(filter),
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("../../fixtures/resources.sql"))]
+    async fn rpc_query_should_stream_reservations(pool: Pool<Postgres>) {
+        let service = RsvpService::new(pool);
+        service
+            .reserve(Request::new(ReserveRequest {
+                reservation: Some(new_rsvp("ocean-view-room-713")),
+                ..Default::default()
+            }))
+            .await
+            .unwrap();
+        service
+            .reserve(Request::new(ReserveRequest {
+                reservation: Some(new_rsvp("ocean-view-room-714")),
+                ..Default::default()
+            }))
+            .await
+            .unwrap();
+
+        let query = ReservationQueryBuilder::default()
+            .user_id("tyrid")
+            .start("2022-12-20T15:00:00-0700".parse::<Timestamp>().unwrap())
+            .end("2022-12-30T12:00:00-0700".parse::<Timestamp>().unwrap())
+            .status(ReservationStatus::Pending as i32)
+            .build()
+            .unwrap();
+        let stream = service
+            .query(Request::new(QueryRequest { query: Some(query) }))
+            .await
+            .unwrap()
+            .into_inner();
+        let responses: Vec<_> = stream.try_collect().await.unwrap();
+
+        assert_eq!(responses.len(), 3);
+        let Some(Data::Pager(pager)) = &responses[0].data else {
+            panic!("expected pager first, got {:?}", responses[0]);
+        };
+        assert_eq!(pager.total, 2);
+        assert_eq!(pager.page, 1);
+        assert!(!pager.has_next);
+        assert!(responses[1..]
+            .iter()
+            .all(|r| matches!(r.data, Some(Data::Reservation(_)))));
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn rpc_resource_crud_should_work(pool: Pool<Postgres>) {
+        let service = RsvpService::new(pool);
+        let resource = abi::Resource {
+            id: "desk-1".into(),
+            name: "Desk 1".into(),
+            kind: "desk".into(),
+            ..Default::default()
+        };
+        let created = service
+            .create_resource(Request::new(CreateResourceRequest {
+                resource: Some(resource.clone()),
+            }))
+            .await
+            .unwrap()
+            .into_inner()
+            .resource
+            .unwrap();
+        assert!(created.active);
+        assert_eq!(created.timezone, "UTC");
+
+        let err = service
+            .create_resource(Request::new(CreateResourceRequest {
+                resource: Some(resource),
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::AlreadyExists);
+
+        let updated = service
+            .update_resource(Request::new(UpdateResourceRequest {
+                id: "desk-1".into(),
+                field_mask: Some(FieldMask {
+                    paths: vec!["timezone".into()],
+                }),
+                resource: Some(abi::Resource {
+                    timezone: "Europe/Berlin".into(),
+                    ..Default::default()
+                }),
+            }))
+            .await
+            .unwrap()
+            .into_inner()
+            .resource
+            .unwrap();
+        assert_eq!(updated.timezone, "Europe/Berlin");
+        assert_eq!(updated.name, "Desk 1");
+
+        service
+            .deactivate_resource(Request::new(DeactivateResourceRequest {
+                id: "desk-1".into(),
+            }))
+            .await
+            .unwrap();
+        let fetched = service
+            .get_resource(Request::new(GetResourceRequest {
+                id: "desk-1".into(),
+            }))
+            .await
+            .unwrap()
+            .into_inner()
+            .resource
+            .unwrap();
+        assert!(!fetched.active);
+
+        let listed = service
+            .list_resources(Request::new(ListResourcesRequest {
+                kind: "desk".into(),
+                include_inactive: false,
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(listed.resources.is_empty());
+
+        let err = service
+            .reserve(Request::new(ReserveRequest {
+                reservation: Some(new_rsvp("desk-1")),
+                ..Default::default()
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::FailedPrecondition);
+    }
+}